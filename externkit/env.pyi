@@ -1,3 +1,175 @@
-def get(name: str) -> str | None:
-    """Get the value of an environment variable."""
+from typing import Any, Callable, Iterator, List, Mapping, Optional, Tuple, TypeVar
+
+_Callback = TypeVar("_Callback", bound=Callable[[List[str]], Any])
+
+def get(name: str, profile: Optional[str] = None) -> Optional[str]:
+    """Get the value of an environment variable.
+
+    Variables are read from the nearest `.externkit` directory above the
+    working directory, or from EXTERNKIT_PROJECT_DIR when it is set. The
+    profile is `profile`, falling back to EXTERNKIT_PROFILE and then to the
+    profile selected with `externkit env use`.
+
+    `${NAME}` and `${NAME:-default}` references in stored values are expanded;
+    a cyclic or undefined reference raises ValueError. Secret references such
+    as `secret+file:///run/secrets/db_pass` are replaced by the secret; a failure to
+    resolve one raises RuntimeError.
+
+    Raises RuntimeError if the store is encrypted and neither
+    EXTERNKIT_PASSPHRASE nor EXTERNKIT_KEYFILE is set.
+    """
+    ...
+
+def get_int(name: str, default: Optional[int] = None, profile: Optional[str] = None) -> Optional[int]:
+    """Get a variable parsed as an integer.
+
+    Returns `default` if the variable is not set. Raises ValueError if the
+    value is not a valid integer.
+    """
+    ...
+
+def get_float(name: str, default: Optional[float] = None, profile: Optional[str] = None) -> Optional[float]:
+    """Get a variable parsed as a float.
+
+    Returns `default` if the variable is not set. Raises ValueError if the
+    value is not a valid float.
+    """
+    ...
+
+def get_bool(name: str, default: Optional[bool] = None, profile: Optional[str] = None) -> Optional[bool]:
+    """Get a variable parsed as a boolean.
+
+    Accepts true/false, yes/no, on/off and 1/0, ignoring case. Returns
+    `default` if the variable is not set. Raises ValueError otherwise.
+    """
+    ...
+
+def get_list(
+    name: str,
+    sep: str = ",",
+    default: Optional[List[str]] = None,
+    profile: Optional[str] = None,
+) -> Optional[List[str]]:
+    """Get a variable split on `sep`, with surrounding whitespace stripped from each item.
+
+    Returns `default` if the variable is not set.
+    """
+    ...
+
+def get_json(name: str, default: Any = None, profile: Optional[str] = None) -> Any:
+    """Get a variable decoded as JSON.
+
+    Returns `default` if the variable is not set. Raises json.JSONDecodeError
+    if the value is not valid JSON.
+    """
+    ...
+
+def get_typed(name: str, default: Any = None, profile: Optional[str] = None) -> Any:
+    """Get a variable with the JSON type it was stored with.
+
+    Values stored with `--type int|bool|json` come back as int, bool, list or
+    dict; strings have their references expanded. An OS environment variable
+    overrides the stored value and is decoded as JSON when the stored value
+    is not a string. Returns `default` if the variable is not set.
+    """
+    ...
+
+def require(name: str, profile: Optional[str] = None) -> str:
+    """Get the value of a variable, raising KeyError if it is not set."""
+    ...
+
+def reload() -> None:
+    """Drop cached variables and secrets so the next read goes back to disk.
+
+    Reads are cached in-process and refreshed automatically when the store
+    files change on disk, so this is only needed to force a re-read.
+    """
+    ...
+
+def on_change(callback: _Callback) -> _Callback:
+    """Call `callback` with the sorted list of changed keys whenever the
+    active profile is edited.
+
+    A key counts as changed when its value with `${NAME}` references expanded
+    does, so editing `A` also reports a `B` stored as `${A}`. Secret
+    references are compared as written.
+
+    The store is polled once per second from a daemon thread. Returns the
+    callback, so it can be used as a decorator.
+    """
+    ...
+
+def register_resolver(prefix: str, callback: Callable[[str], str]) -> None:
+    """Resolve stored values starting with `secret+` and `prefix` by calling
+    `callback` with the rest of the value.
+
+    Replaces the resolver registered for the same prefix, including the
+    built-in `file://`, `cmd:` and `keyring:` ones. Exceptions raised by
+    `callback` are reported as RuntimeError.
+    """
+    ...
+
+def set_secret_cache_ttl(seconds: Optional[float]) -> None:
+    """Keep resolved secrets for `seconds`, or until `reload()` when `None`
+    (the default). Zero disables caching.
+    """
+    ...
+
+def set(name: str, value: Any, profile: Optional[str] = None) -> None:
+    """Store `value` under `name` in the active profile, or `profile`.
+
+    Writes go through the same locked, audited store as `externkit env set`.
+    Strings are stored as they are; other values are stored as JSON and read
+    back with `get_typed`. Raises ValueError for an empty string, as the CLI
+    does, and for `None`; use `delete` to remove a variable.
+    """
+    ...
+
+def delete(name: str, profile: Optional[str] = None) -> None:
+    """Remove `name` from the active profile, or `profile`.
+
+    Raises KeyError if the profile does not store it.
+    """
+    ...
+
+def load_into_os(override: bool = False, profile: Optional[str] = None) -> List[str]:
+    """Copy the stored variables into `os.environ` so libraries that read it
+    see them.
+
+    Values are exported the way `externkit run` exports them. Variables that
+    are already set are kept unless `override` is true. Returns the names
+    that were set.
+    """
+    ...
+
+class EnvStore(Mapping[str, str]):
+    """The stored variables of a profile as a mapping.
+
+    Keys are the stored names, with nested objects flattened to
+    `PARENT__CHILD` names as `externkit run` exports them. Values are read
+    like `get`, so the OS environment wins. Assigning or deleting a key
+    writes to the store like `set` and `delete`.
+
+    Assigning or deleting a flattened name raises KeyError naming the
+    nested variable to change instead. For that reason the mapping is not a
+    MutableMapping and has no `update`, `pop` or `clear`.
+    """
+
+    def __getitem__(self, name: str) -> str: ...
+    def __setitem__(self, name: str, value: Any) -> None: ...
+    def __delitem__(self, name: str) -> None: ...
+    def __contains__(self, name: object) -> bool: ...
+    def __iter__(self) -> Iterator[str]: ...
+    def __len__(self) -> int: ...
+    def keys(self) -> List[str]: ...  # type: ignore[override]
+    def values(self) -> List[str]: ...  # type: ignore[override]
+    def items(self) -> List[Tuple[str, str]]: ...  # type: ignore[override]
+    def get(self, name: str, default: Any = None) -> Any: ...  # type: ignore[override]
+
+store: EnvStore
+"""The stored variables of the active profile."""
+
+def all(profile: Optional[str] = None) -> EnvStore:
+    """The stored variables of the active profile, or `profile`, as a
+    mapping."""
     ...
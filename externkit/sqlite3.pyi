@@ -1,28 +1,160 @@
-from typing import List, Optional, Tuple, Union, Any
+from typing import (
+    Any,
+    Iterable,
+    Callable,
+    Dict,
+    Iterator,
+    List,
+    Literal,
+    Mapping,
+    Optional,
+    Sequence,
+    Tuple,
+    Type,
+    TypeVar,
+    Union,
+)
+
+_T = TypeVar("_T")
+
+RowFactory = Union[Literal["tuple", "dict", "row"], Type[tuple], Type[dict], Type["Row"]]
+Params = Union[Sequence[Any], Mapping[str, Any]]
+
+def register_adapter(type: Type[_T], adapter: Callable[[_T], Any]) -> None:
+    """Bind instances of `type` as the value `adapter` returns for them.
+
+    The adapter must return an int, float, str, bytes, bool, None, datetime,
+    date or Decimal. Adapters for a base class also apply to its subclasses;
+    registering `type` again replaces its adapter.
+    """
+    ...
+
+class Row:
+    """A result row with both index and column-name access, like a namedtuple.
+
+    `row[0]`, `row["name"]` and `row.name` return the same value. Names match
+    case-insensitively when no column has the exact name. Rows compare equal
+    to tuples with the same values.
+    """
+
+    def __len__(self) -> int: ...
+    def __getitem__(self, key: Union[int, slice, str]) -> Any: ...
+    def __getattr__(self, name: str) -> Any: ...
+    def __iter__(self) -> Iterator[Any]: ...
+    def __contains__(self, value: object) -> bool: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __hash__(self) -> int: ...
+    def keys(self) -> List[str]:
+        """Column names in result order."""
+        ...
+    def _asdict(self) -> Dict[str, Any]:
+        """The row as a dict of column name to value."""
+        ...
+
+class Cursor:
+    """Rows of a statement run with `SqliteClient.execute`, read lazily.
+
+    Rows are stepped from SQLite one at a time as they are fetched, so large
+    results never have to fit in memory. Other calls on the client may run
+    between fetches. The statement is finalized once every row has been read
+    or `close()` is called.
+    """
+
+    arraysize: int
+    """How many rows `fetchmany()` returns by default. Defaults to 1."""
+
+    @property
+    def description(self) -> List[Tuple[str, None, None, None, None, None, None]]:
+        """A DB-API 7-tuple per result column; only the name is filled in."""
+        ...
+
+    def fetchone(self) -> Optional[Any]:
+        """The next row, or None when there are no more."""
+        ...
+
+    def fetchmany(self, size: Optional[int] = None) -> List[Any]:
+        """Up to `size` rows, `arraysize` by default. Fewer only at the end."""
+        ...
+
+    def fetchall(self) -> List[Any]:
+        """All remaining rows."""
+        ...
+
+    def close(self) -> None:
+        """Finalize the statement without reading the remaining rows."""
+        ...
+
+    def __iter__(self) -> Iterator[Any]: ...
+    def __next__(self) -> Any: ...
+
+class Transaction:
+    """Context manager returned by `SqliteClient.transaction()`."""
+
+    def __enter__(self) -> "Transaction": ...
+    def __exit__(self, exc_type: Any, exc_value: Any, traceback: Any) -> bool: ...
+
+TransactionMode = Literal["deferred", "immediate", "exclusive"]
 
 class SqliteClient:
     """SQLite database client for Python."""
     
-    def __init__(self, connection_string: str) -> None:
+    def __init__(self, connection_string: str, row_factory: Optional[RowFactory] = None) -> None:
         """Initialize SQLite connection.
         
         Args:
             connection_string: Path to SQLite database file or ':memory:' for in-memory database
+            row_factory: How rows are returned by default: `tuple` (the default),
+                `dict` or `Row`. The names "tuple", "dict" and "row" work too.
         """
         ...
     
-    def query(self, query: str, params: List[Any] = ...) -> List[Union[Any, Tuple[Any, ...]]]:
+    def query(
+        self,
+        query: str,
+        params: Optional[Params] = None,
+        *,
+        row_factory: Optional[RowFactory] = None,
+        scalar: bool = False,
+    ) -> List[Any]:
         """Execute a SQL query and return results.
         
         Args:
-            query: SQL query string with ? placeholders
-            params: List of parameters to bind to the query
+            query: SQL query string with ? placeholders, or :name, @name and
+                $name placeholders when params is a dict
+            params: Sequence of parameters for ? placeholders, or a dict for
+                named ones; dict keys may include the : or @ prefix. int,
+                float, str, bytes, bool and None bind as the matching SQLite
+                types; datetime, date and Decimal bind as ISO 8601 or decimal
+                text. Other types raise TypeError unless an adapter is
+                registered. A missing named value raises ValueError.
+            row_factory: Overrides the client's row_factory for this query
+            scalar: Return the values of a single-column result directly.
+                Raises ValueError when the query returns more columns.
             
         Returns:
-            List of results. Single column returns scalar values, multiple columns return tuples
+            List of rows built by row_factory, or of values with scalar=True
         """
         ...
     
+    def execute(
+        self,
+        sql: str,
+        params: Optional[Params] = None,
+        *,
+        row_factory: Optional[RowFactory] = None,
+    ) -> Cursor:
+        """Execute a SQL statement and return a cursor over its rows.
+
+        The statement runs immediately, so errors are raised here, but its
+        rows are only read as the cursor is fetched from or iterated.
+
+        Args:
+            sql: SQL statement, with placeholders as for `query`
+            params: Parameters bound as for `query`
+            row_factory: Overrides the client's row_factory for this cursor
+        """
+        ...
+
     def create_table(self, table_name: str, columns: List[Tuple[str, str]]) -> None:
         """Create a table if it doesn't exist.
         
@@ -38,25 +170,61 @@ class SqliteClient:
         Args:
             table_name: Name of the table
             columns: List of column names
-            values: List of values to insert
+            values: List of values to insert, bound like `query` parameters
         """
         ...
     
+    def executemany(self, sql: str, seq_of_params: Iterable[Params]) -> int:
+        """Execute `sql` once per parameter set with one prepared statement.
+
+        Args:
+            sql: SQL statement that does not return rows
+            seq_of_params: Any iterable of parameter sequences or dicts, bound
+                like `query` parameters. Generators are consumed lazily and
+                must not use this client.
+
+        Returns:
+            Total number of rows changed. Either every parameter set is
+            applied or, when one fails, none are: the batch runs in a
+            transaction, or a savepoint inside an active one.
+        """
+        ...
+
+    def insert_many(
+        self, table_name: str, columns: List[str], rows: Iterable[Sequence[Any]]
+    ) -> int:
+        """Insert every row of `rows` like `executemany` does.
+
+        Args:
+            table_name: Name of the table
+            columns: List of column names
+            rows: Any iterable of value sequences, one per row
+
+        Returns:
+            Number of inserted rows
+        """
+        ...
+
     def select(
         self, 
         table_name: str, 
         columns: List[str], 
-        where_clause: Optional[str] = None
-    ) -> List[Union[Any, Tuple[Any, ...]]]:
+        where_clause: Optional[str] = None,
+        *,
+        row_factory: Optional[RowFactory] = None,
+        scalar: bool = False,
+    ) -> List[Any]:
         """Select records from the table.
         
         Args:
             table_name: Name of the table
             columns: List of column names to select
             where_clause: Optional WHERE clause (without the WHERE keyword)
+            row_factory: Overrides the client's row_factory, as for `query`
+            scalar: Return the values of a single selected column directly
             
         Returns:
-            List of results. Single column returns scalar values, multiple columns return tuples
+            List of rows built by row_factory, or of values with scalar=True
         """
         ...
     
@@ -85,6 +253,36 @@ class SqliteClient:
         """
         ...
     
+    def begin(self, mode: TransactionMode = "deferred") -> None:
+        """Start a transaction with BEGIN DEFERRED, IMMEDIATE or EXCLUSIVE.
+
+        Raises RuntimeError when a transaction is already active; use
+        `transaction()` to nest.
+        """
+        ...
+
+    def commit(self) -> None:
+        """Commit the active transaction. Does nothing without one."""
+        ...
+
+    def rollback(self) -> None:
+        """Roll back the active transaction. Does nothing without one."""
+        ...
+
+    @property
+    def in_transaction(self) -> bool:
+        """Whether a transaction is active."""
+        ...
+
+    def transaction(self, mode: TransactionMode = "deferred") -> Transaction:
+        """A block that commits when it succeeds and rolls back when it raises.
+
+        Outside a transaction the block begins one with `mode`; inside one it
+        uses a savepoint, so a failing inner block only undoes its own
+        changes. The exception is re-raised either way.
+        """
+        ...
+
     def close(self) -> None:
         """Close the database connection."""
         ...
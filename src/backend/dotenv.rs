@@ -0,0 +1,208 @@
+/// Formats a value so it reads back unchanged from a dotenv file. Plain
+/// values are written bare; anything else is double quoted and escaped.
+pub fn quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-.,/:@+%".contains(c));
+    if plain {
+        return value.to_string();
+    }
+
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '$' => quoted.push_str("\\$"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parses dotenv content into ordered `(key, value)` pairs.
+///
+/// Supports `export` prefixes, comments, single quotes (literal), double
+/// quotes (escapes), multiline quoted values and `$VAR`, `${VAR}` and
+/// `${VAR:-default}` interpolation. References resolve against keys defined
+/// earlier in the file first and `lookup` second.
+pub fn parse(
+    content: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<Vec<(String, String)>, ParseError> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line_number = index + 1;
+        let error = |message: &str| ParseError {
+            line: line_number,
+            message: message.to_string(),
+        };
+        let line = lines[index].trim_start();
+        index += 1;
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = match line.strip_prefix("export") {
+            Some(rest) if rest.starts_with([' ', '\t']) => rest.trim_start(),
+            _ => line,
+        };
+        let Some((key, rest)) = line.split_once('=') else {
+            return Err(error("expected KEY=VALUE"));
+        };
+        let key = key.trim_end();
+        let valid_key = !key.is_empty()
+            && !key.starts_with(|c: char| c.is_ascii_digit())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.' || c == '-');
+        if !valid_key {
+            return Err(error(&format!("invalid key '{}'", key)));
+        }
+
+        let resolve = |name: &str| {
+            entries
+                .iter()
+                .rev()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+                .or_else(|| lookup(name))
+        };
+
+        let rest = rest.trim_start();
+        let value = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let mut raw = rest[1..].to_string();
+                let end = loop {
+                    if let Some(end) = find_closing_quote(&raw, quote) {
+                        break end;
+                    }
+                    if index >= lines.len() {
+                        return Err(error("unterminated quoted value"));
+                    }
+                    raw.push('\n');
+                    raw.push_str(lines[index]);
+                    index += 1;
+                };
+                let trailing = raw[end + 1..].trim();
+                if !trailing.is_empty() && !trailing.starts_with('#') {
+                    return Err(error("unexpected characters after closing quote"));
+                }
+                raw.truncate(end);
+                if quote == '\'' {
+                    raw
+                } else {
+                    expand(&raw, true, &resolve)
+                }
+            }
+            _ => {
+                let raw = match rest.find(" #").or_else(|| rest.find("\t#")) {
+                    Some(comment) => &rest[..comment],
+                    None => rest,
+                };
+                expand(raw.trim_end(), false, &resolve)
+            }
+        };
+
+        entries.retain(|(existing, _)| existing != key);
+        entries.push((key.to_string(), value));
+    }
+    Ok(entries)
+}
+
+fn find_closing_quote(raw: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (position, c) in raw.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(position);
+        }
+    }
+    None
+}
+
+fn expand(raw: &str, escapes: bool, resolve: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('r') => value.push('\r'),
+                Some('t') => value.push('\t'),
+                Some(c @ ('"' | '\\' | '$')) => value.push(c),
+                Some(c) => {
+                    value.push('\\');
+                    value.push(c);
+                }
+                None => value.push('\\'),
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut reference = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    reference.push(c);
+                }
+                if !closed {
+                    value.push_str("${");
+                    value.push_str(&reference);
+                    continue;
+                }
+                let (name, default) = match reference.split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (reference.as_str(), None),
+                };
+                match (resolve(name).filter(|v| !v.is_empty()), default) {
+                    (Some(resolved), _) => value.push_str(&resolved),
+                    (None, Some(default)) => value.push_str(default),
+                    (None, None) => {}
+                }
+            }
+            '$' if chars
+                .peek()
+                .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_') =>
+            {
+                let mut name = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_ascii_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    name.push(c);
+                    chars.next();
+                }
+                value.push_str(&resolve(&name).unwrap_or_default());
+            }
+            c => value.push(c),
+        }
+    }
+    value
+}
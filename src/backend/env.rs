@@ -1,23 +1,246 @@
 use colored::Colorize;
+use externkit::env::audit;
+use externkit::env::crypto::{self, KeySource};
+use externkit::env::profiles::{self, ProfileConfig, DEFAULT_PROFILE};
+use externkit::env::repair;
+use externkit::env::schema::{self, Schema};
+use externkit::env::storage::{self, StoreLock};
+use externkit::env::value::{self, Value};
+use externkit::env::{Error, Store};
 
-fn load_env_vars() -> std::collections::HashMap<String, String> {
-    let content =
-        std::fs::read_to_string(".externkit/environment_variables.json").unwrap_or_default();
-    if content.is_empty() {
-        std::collections::HashMap::new()
-    } else {
-        serde_json::from_str(&content).unwrap_or_default()
+/// Store selection flags shared by every `env` subcommand.
+pub struct StoreOptions {
+    pub project_dir: std::path::PathBuf,
+    pub keyfile: Option<String>,
+    pub profile: Option<String>,
+    /// Whether a missing passphrase may be asked for on the terminal.
+    pub prompt: bool,
+}
+
+impl StoreOptions {
+    pub fn from_matches(matches: &clap::ArgMatches, project_dir: &std::path::Path) -> Self {
+        StoreOptions {
+            project_dir: project_dir.to_path_buf(),
+            keyfile: matches.get_one::<String>("keyfile").cloned(),
+            profile: matches.get_one::<String>("profile").cloned(),
+            prompt: true,
+        }
+    }
+
+    fn key_source(&self) -> std::io::Result<KeySource> {
+        if let Some(path) = &self.keyfile {
+            return Ok(KeySource::Keyfile(path.into()));
+        }
+        if let Some(key_source) = KeySource::from_env() {
+            return Ok(key_source);
+        }
+        if !self.prompt {
+            return Err(Error::KeyRequired.into());
+        }
+        crate::backend::utils::prompt_passphrase("Passphrase: ").map(KeySource::Passphrase)
+    }
+
+    fn new_key_source(&self) -> std::io::Result<KeySource> {
+        if let Some(path) = &self.keyfile {
+            return Ok(KeySource::Keyfile(path.into()));
+        }
+        if let Some(key_source) = KeySource::from_env() {
+            return Ok(key_source);
+        }
+        crate::backend::utils::prompt_new_passphrase().map(KeySource::Passphrase)
+    }
+
+    fn resolve_profile(&self) -> externkit::env::Result<String> {
+        let config = ProfileConfig::load(&self.project_dir)?;
+        let profile = config.resolve_profile(self.profile.as_deref());
+        if !config.exists(&profile) {
+            return Err(Error::ProfileNotFound(profile));
+        }
+        Ok(profile)
+    }
+
+    /// Opens the selected profile, asking for the key only when the profile,
+    /// or with `inherited` one of its bases, is encrypted. A `locked` store
+    /// holds the project lock until it is dropped.
+    pub(crate) fn open_store(
+        &self,
+        locked: bool,
+        inherited: bool,
+    ) -> externkit::env::Result<Store> {
+        let profile = self.resolve_profile()?;
+        let profiles = if inherited {
+            ProfileConfig::load(&self.project_dir)?.chain(&profile)?
+        } else {
+            vec![profile.clone()]
+        };
+        let key_source = if profiles
+            .iter()
+            .any(|name| externkit::env::is_encrypted(&self.project_dir, name))
+        {
+            Some(self.key_source()?)
+        } else {
+            None
+        };
+        if locked {
+            Store::load_locked(&self.project_dir, Some(&profile), key_source)
+        } else {
+            Store::load(&self.project_dir, Some(&profile), key_source)
+        }
+    }
+}
+
+/// Opens the selected profile for editing. Prints the error and returns
+/// `None` if it cannot be read.
+fn load_env_vars(options: &StoreOptions) -> Option<Store> {
+    match options.open_store(true, false) {
+        Ok(store) => Some(store),
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            None
+        }
+    }
+}
+
+/// Writes the variables back. Prints the error and returns `false` if the
+/// write failed; a failure to update the audit log is only a warning.
+pub(crate) fn save_env_vars(store: &mut Store, action: &str) -> bool {
+    match store.save(action) {
+        Ok(()) => true,
+        Err(e @ Error::Audit(_)) => {
+            println!("{}", e.to_string().yellow());
+            true
+        }
+        Err(e) => {
+            println!(
+                "{}",
+                format!("Failed to save environment variables: {}", e).red()
+            );
+            false
+        }
     }
 }
 
-fn save_env_vars(env_vars: &std::collections::HashMap<String, String>) {
-    let content = serde_json::to_string_pretty(env_vars).unwrap();
-    std::fs::write(".externkit/environment_variables.json", content).unwrap();
+/// Checks the references of the values about to be saved. Prints the error
+/// and returns `false` when one does not resolve.
+pub(crate) fn check_references<'k>(store: &Store, keys: impl IntoIterator<Item = &'k str>) -> bool {
+    match store.check_references(keys) {
+        Ok(()) => true,
+        Err(e) => {
+            let hint = match e {
+                Error::UndefinedReference { .. } | Error::UnterminatedReference(_) => {
+                    " Write $$ for a literal $."
+                }
+                _ => "",
+            };
+            println!("{}", format!("{}{}", e, hint).red());
+            false
+        }
+    }
 }
 
-pub fn add_env_var(name: &str, value: &str) {
-    let mut env_vars = load_env_vars();
-    if env_vars.contains_key(name) {
+/// Removes a store file after copying it into the backup rotation.
+fn remove_store_file(project_dir: &std::path::Path, path: &std::path::Path) {
+    if let Err(e) = storage::remove(project_dir, path) {
+        println!(
+            "{}",
+            format!("Failed to remove {}: {}", path.display(), e).red()
+        );
+    }
+}
+
+/// Deletes a plaintext store file and its backups because an encrypted
+/// copy of the profile now exists.
+fn purge_plaintext_store(project_dir: &std::path::Path, path: &std::path::Path) {
+    if let Err(e) = storage::purge(project_dir, path) {
+        println!(
+            "{}",
+            format!("Failed to remove {}: {}", path.display(), e).red()
+        );
+    }
+}
+
+/// Loads the selected profile merged with the profiles it inherits from,
+/// along with the names from the shared layer.
+pub(crate) fn load_merged_env_vars(
+    options: &StoreOptions,
+) -> Option<(
+    std::collections::HashMap<String, Value>,
+    std::collections::HashSet<String>,
+)> {
+    match options
+        .open_store(false, true)
+        .and_then(|store| store.merged_with_shared_keys())
+    {
+        Ok(env_vars) => Some(env_vars),
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            None
+        }
+    }
+}
+
+/// Like `load_merged_env_vars`, with `${NAME}` references expanded.
+pub(crate) fn load_resolved_env_vars(
+    options: &StoreOptions,
+) -> Option<std::collections::HashMap<String, String>> {
+    match options
+        .open_store(false, true)
+        .and_then(|store| store.resolved())
+    {
+        Ok(env_vars) => Some(env_vars),
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            None
+        }
+    }
+}
+
+/// Like `load_resolved_env_vars`, with nested objects flattened to
+/// `PARENT__CHILD` names as processes and dotenv files receive them. With
+/// `secrets`, secret references are replaced by the secrets.
+pub(crate) fn load_exported_env_vars(
+    options: &StoreOptions,
+    secrets: bool,
+) -> Option<std::collections::HashMap<String, String>> {
+    match options.open_store(false, true).and_then(|store| {
+        if secrets {
+            store.resolved_secrets(externkit::env::secrets::registry())
+        } else {
+            store.resolved_flat()
+        }
+    }) {
+        Ok(env_vars) => Some(env_vars),
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            None
+        }
+    }
+}
+
+/// Parses a value given on the command line as the type picked with
+/// `--type`.
+fn parse_value(value: &str, value_type: &str) -> Result<Value, String> {
+    match value_type {
+        "int" => value
+            .trim()
+            .parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| "Value is not a valid integer.".to_string()),
+        "bool" => schema::parse_bool(value)
+            .map(Value::Bool)
+            .ok_or_else(|| "Value is not a valid boolean.".to_string()),
+        "json" => {
+            serde_json::from_str(value).map_err(|e| format!("Value is not valid JSON: {}", e))
+        }
+        _ => Ok(Value::String(value.to_string())),
+    }
+}
+
+pub fn add_env_var(name: &str, value: &str, value_type: &str, options: &StoreOptions) {
+    let Some(mut store) = load_env_vars(options) else {
+        return;
+    };
+    if store.contains_key(name) {
         println!(
             "{}",
             format!(
@@ -35,17 +258,28 @@ pub fn add_env_var(name: &str, value: &str) {
         );
         return;
     }
-    env_vars.insert(name.to_string(), value.to_string());
-    save_env_vars(&env_vars);
+    let typed = match parse_value(value, value_type) {
+        Ok(typed) => typed,
+        Err(e) => {
+            println!("{}", e.red());
+            return;
+        }
+    };
+    store.set(name, typed);
+    if !check_references(&store, [name]) || !save_env_vars(&mut store, "add") {
+        return;
+    }
     println!(
         "{}",
         format!("Added environment variable: {}={}", name, value).green()
     );
 }
 
-pub fn delete_env_var(name: &str) {
-    let mut env_vars = load_env_vars();
-    if !env_vars.contains_key(name) {
+pub fn delete_env_var(name: &str, options: &StoreOptions) {
+    let Some(mut store) = load_env_vars(options) else {
+        return;
+    };
+    if !store.contains_key(name) {
         println!(
             "{}",
             format!("Environment variable '{}' does not exist.", name).yellow()
@@ -56,16 +290,20 @@ pub fn delete_env_var(name: &str) {
         println!("{}", "Environment variable name cannot be empty.".red());
         return;
     }
-    env_vars.remove(name);
-    save_env_vars(&env_vars);
+    store.remove(name);
+    if !save_env_vars(&mut store, "delete") {
+        return;
+    }
     println!(
         "{}",
         format!("Deleted environment variable: {}", name).green()
     );
 }
-pub fn update_env_var(name: &str, value: &str) {
-    let mut env_vars = load_env_vars();
-    if !env_vars.contains_key(name) {
+pub fn update_env_var(name: &str, value: &str, value_type: &str, options: &StoreOptions) {
+    let Some(mut store) = load_env_vars(options) else {
+        return;
+    };
+    if !store.contains_key(name) {
         println!(
             "{}",
             format!(
@@ -83,10 +321,1269 @@ pub fn update_env_var(name: &str, value: &str) {
         );
         return;
     }
-    env_vars.insert(name.to_string(), value.to_string());
-    save_env_vars(&env_vars);
+    let typed = match parse_value(value, value_type) {
+        Ok(typed) => typed,
+        Err(e) => {
+            println!("{}", e.red());
+            return;
+        }
+    };
+    store.set(name, typed);
+    if !check_references(&store, [name]) || !save_env_vars(&mut store, "update") {
+        return;
+    }
     println!(
         "{}",
         format!("Updated environment variable: {}={}", name, value).green()
     );
 }
+
+/// Where `env set` takes its values from besides the `KEY=VALUE` arguments.
+pub enum SetInput {
+    Args,
+    /// `KEY=VALUE` lines on stdin.
+    Lines,
+    /// The value of the single key given, read from stdin so it stays out of
+    /// the shell history.
+    Value,
+}
+
+/// Parses `KEY=VALUE` lines, skipping blank lines and `#` comments. Values
+/// are taken literally; an `export ` prefix is allowed.
+fn parse_pairs(content: &str) -> Result<Vec<(String, String)>, String> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(number, line)| {
+            let line = line.trim_start();
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            line.split_once('=')
+                .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+                .ok_or_else(|| format!("line {}: expected KEY=VALUE", number))
+        })
+        .collect()
+}
+
+fn read_stdin_value(key: &str) -> std::io::Result<String> {
+    use std::io::IsTerminal;
+
+    if std::io::stdin().is_terminal() {
+        return crate::backend::utils::prompt_passphrase(&format!("Value for {}: ", key));
+    }
+    let mut value = std::io::read_to_string(std::io::stdin())?;
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    Ok(value)
+}
+
+/// Collects the pairs `env set` applies. Every pair is checked before any
+/// is applied.
+fn collect_pairs(
+    args: &[String],
+    input: SetInput,
+    value_type: &str,
+) -> Result<Vec<(String, Value)>, String> {
+    let mut pairs = Vec::new();
+    match input {
+        SetInput::Value => {
+            let [key] = args else {
+                return Err("--value-stdin takes exactly one KEY.".to_string());
+            };
+            let value = read_stdin_value(key).map_err(|e| e.to_string())?;
+            pairs.push((key.clone(), value));
+        }
+        SetInput::Args | SetInput::Lines => {
+            for arg in args {
+                let (key, value) = arg
+                    .split_once('=')
+                    .ok_or_else(|| format!("Expected KEY=VALUE, got '{}'.", arg))?;
+                pairs.push((key.to_string(), value.to_string()));
+            }
+            if matches!(input, SetInput::Lines) {
+                let content =
+                    std::io::read_to_string(std::io::stdin()).map_err(|e| e.to_string())?;
+                pairs.extend(
+                    parse_pairs(&content).map_err(|e| format!("Failed to parse stdin: {}", e))?,
+                );
+            }
+        }
+    }
+    if pairs.is_empty() {
+        return Err("No environment variables given.".to_string());
+    }
+
+    pairs
+        .into_iter()
+        .map(|(key, value)| {
+            if key.is_empty() || value.is_empty() {
+                return Err("Environment variable name and value cannot be empty.".to_string());
+            }
+            let typed = parse_value(&value, value_type).map_err(|e| format!("{}: {}", key, e))?;
+            Ok((key, typed))
+        })
+        .collect()
+}
+
+/// Adds or updates every pair in a single write. Nothing is written when any
+/// pair is invalid. Values are not echoed since they are often secrets.
+pub fn set_env_vars(args: &[String], input: SetInput, value_type: &str, options: &StoreOptions) {
+    let pairs = match collect_pairs(args, input, value_type) {
+        Ok(pairs) => pairs,
+        Err(e) => {
+            println!("{}", e.red());
+            std::process::exit(1);
+        }
+    };
+    let Some(mut store) = load_env_vars(options) else {
+        std::process::exit(1);
+    };
+
+    let mut changed = std::collections::BTreeSet::new();
+    for (key, value) in pairs {
+        if store.get(&key) != Some(&value) {
+            changed.insert(key.clone());
+        }
+        store.set(key, value);
+    }
+    if changed.is_empty() {
+        println!("{}", "No changes.".green());
+        return;
+    }
+    if !check_references(&store, changed.iter().map(String::as_str))
+        || !save_env_vars(&mut store, "set")
+    {
+        std::process::exit(1);
+    }
+    let keys: Vec<&str> = changed.iter().map(String::as_str).collect();
+    println!(
+        "{}",
+        format!(
+            "Set {} environment variable(s): {}",
+            keys.len(),
+            keys.join(", ")
+        )
+        .green()
+    );
+}
+
+/// Removes every key in a single write. Keys that are not set are skipped,
+/// so the command can be repeated safely.
+pub fn unset_env_vars(keys: &[String], options: &StoreOptions) {
+    let Some(mut store) = load_env_vars(options) else {
+        std::process::exit(1);
+    };
+
+    let mut removed = std::collections::BTreeSet::new();
+    for key in keys {
+        if store.remove(key).is_some() {
+            removed.insert(key.as_str());
+        }
+    }
+    if removed.is_empty() {
+        println!("{}", "No changes.".green());
+        return;
+    }
+    if !save_env_vars(&mut store, "unset") {
+        std::process::exit(1);
+    }
+    let removed: Vec<&str> = removed.into_iter().collect();
+    println!(
+        "{}",
+        format!(
+            "Unset {} environment variable(s): {}",
+            removed.len(),
+            removed.join(", ")
+        )
+        .green()
+    );
+}
+
+pub fn lock_env_vars(options: &StoreOptions) {
+    let result = options
+        .resolve_profile()
+        .and_then(|profile| Store::load_locked(&options.project_dir, Some(&profile), None))
+        .and_then(|store| {
+            if store.is_encrypted() {
+                return Ok(None);
+            }
+            let key_source = options.new_key_source()?;
+            Ok(Some((store, key_source)))
+        });
+    let (mut store, key_source) = match result {
+        Ok(Some(loaded)) => loaded,
+        Ok(None) | Err(Error::KeyRequired) => {
+            println!(
+                "{}",
+                "Environment variables are already encrypted.".yellow()
+            );
+            return;
+        }
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            return;
+        }
+    };
+
+    if let Err(e) = store.encrypt(key_source) {
+        println!(
+            "{}",
+            format!("Failed to save environment variables: {}", e).red()
+        );
+        return;
+    }
+    println!(
+        "{}",
+        format!("Encrypted {} environment variable(s).", store.len()).green()
+    );
+}
+
+pub fn unlock_env_vars(options: &StoreOptions) {
+    let Some(mut store) = load_env_vars(options) else {
+        return;
+    };
+    if !store.is_encrypted() {
+        println!("{}", "Environment variables are not encrypted.".yellow());
+        return;
+    }
+    if let Err(e) = store.decrypt() {
+        println!(
+            "{}",
+            format!("Failed to save environment variables: {}", e).red()
+        );
+        return;
+    }
+    println!(
+        "{}",
+        format!("Decrypted {} environment variable(s).", store.len()).green()
+    );
+}
+
+pub fn use_profile(
+    profile: Option<&String>,
+    extends: Option<&String>,
+    project_dir: &std::path::Path,
+) {
+    if let Err(e) = switch_profile(profile, extends, project_dir) {
+        println!("{}", e.to_string().red());
+    }
+}
+
+fn switch_profile(
+    profile: Option<&String>,
+    extends: Option<&String>,
+    project_dir: &std::path::Path,
+) -> externkit::env::Result<()> {
+    let Some(profile) = profile else {
+        let config = ProfileConfig::load(project_dir)?;
+        let active = config.resolve_profile(None);
+        for name in config.names() {
+            let base = config
+                .profiles
+                .get(&name)
+                .and_then(|entry| entry.extends.clone())
+                .map(|base| format!(" (extends {})", base))
+                .unwrap_or_default();
+            if name == active {
+                println!("{}", format!("* {}{}", name, base).green().bold());
+            } else {
+                println!("  {}{}", name, base);
+            }
+        }
+        return Ok(());
+    };
+
+    profiles::validate_name(profile)?;
+    let _lock = StoreLock::acquire(project_dir)?;
+    let mut config = ProfileConfig::load(project_dir)?;
+    let created = !config.exists(profile);
+    if let Some(base) = extends {
+        if !config.exists(base) {
+            return Err(Error::ProfileNotFound(base.clone()));
+        }
+    }
+    if created || extends.is_some() {
+        let entry = config.profiles.entry(profile.clone()).or_default();
+        if let Some(base) = extends {
+            entry.extends = Some(base.clone());
+        }
+        config.chain(profile)?;
+    }
+    if created {
+        storage::write_atomic(&profiles::store_path(project_dir, profile), b"{\n}")?;
+    }
+
+    config.active = if profile == DEFAULT_PROFILE {
+        None
+    } else {
+        Some(profile.clone())
+    };
+    config.save(project_dir)?;
+
+    let message = if created {
+        format!("Created and switched to profile '{}'", profile)
+    } else {
+        format!("Switched to profile '{}'", profile)
+    };
+    match extends {
+        Some(base) => println!("{}", format!("{} (extends '{}')", message, base).green()),
+        None => println!("{}", message.green()),
+    }
+    Ok(())
+}
+
+pub(crate) fn mask(value: &str) -> String {
+    if value.is_empty() {
+        String::new()
+    } else {
+        "********".to_string()
+    }
+}
+
+fn matches_filter(key: &str, filter: Option<&glob::Pattern>, prefix: Option<&String>) -> bool {
+    match (filter, prefix) {
+        (Some(pattern), _) => pattern.matches(key),
+        (None, Some(prefix)) => key.starts_with(prefix.as_str()),
+        (None, None) => true,
+    }
+}
+
+pub fn list_env_vars(
+    filter: Option<&String>,
+    reveal: bool,
+    raw: bool,
+    format: &str,
+    options: &StoreOptions,
+) {
+    let (merged, origins) = match options
+        .open_store(false, true)
+        .and_then(|store| Ok((store.merged()?, store.origins()?)))
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            return;
+        }
+    };
+    // Dotenv output gets nested objects flattened, like `export` does.
+    let typed = if format == "dotenv" {
+        value::flatten(&merged)
+    } else {
+        merged
+    };
+    let env_vars = if raw {
+        typed
+            .iter()
+            .filter_map(|(key, value)| value::as_env_string(value).map(|text| (key.clone(), text)))
+            .collect()
+    } else {
+        match externkit::env::interpolate::resolve_nested(&typed) {
+            Ok(env_vars) => env_vars,
+            Err(e) => {
+                println!("{}", e.to_string().red());
+                return;
+            }
+        }
+    };
+
+    // Filters containing glob metacharacters are matched as globs, anything
+    // else as a key prefix.
+    let pattern = match filter.filter(|f| f.contains(['*', '?', '['])) {
+        Some(filter) => match glob::Pattern::new(filter) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                println!("{}", format!("Invalid pattern '{}': {}", filter, e).red());
+                return;
+            }
+        },
+        None => None,
+    };
+    let env_vars: std::collections::BTreeMap<String, String> = env_vars
+        .into_iter()
+        .filter(|(key, _)| matches_filter(key, pattern.as_ref(), filter))
+        .map(|(key, value)| {
+            let value = if reveal { value } else { mask(&value) };
+            (key, value)
+        })
+        .collect();
+
+    match format {
+        "json" => {
+            // Revealed values keep their JSON type.
+            let env_vars: std::collections::BTreeMap<&String, Value> = env_vars
+                .iter()
+                .map(|(key, text)| {
+                    let value = match typed.get(key) {
+                        Some(value) if reveal && !value.is_string() => value.clone(),
+                        _ => Value::String(text.clone()),
+                    };
+                    (key, value)
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&env_vars).unwrap())
+        }
+        "dotenv" => {
+            for (key, value) in &env_vars {
+                println!("{}={}", key, crate::backend::dotenv::quote(value));
+            }
+        }
+        _ => {
+            if env_vars.is_empty() {
+                println!("{}", "No environment variables found.".yellow());
+                return;
+            }
+            let layers: std::collections::BTreeMap<&String, String> = env_vars
+                .keys()
+                .map(|key| {
+                    let layer = origins.get(key).map(ToString::to_string);
+                    (key, layer.unwrap_or_default())
+                })
+                .collect();
+            let width = env_vars.keys().map(|key| key.len()).max().unwrap_or(0);
+            let layer_width = layers.values().map(|layer| layer.len()).max().unwrap_or(0);
+            let layer_width = layer_width.max("LAYER".len());
+            println!(
+                "{}",
+                format!("{:<width$}  {:<layer_width$}  VALUE", "KEY", "LAYER").bold()
+            );
+            for (key, value) in &env_vars {
+                println!(
+                    "{}  {:<layer_width$}  {}",
+                    format!("{:<width$}", key).cyan(),
+                    layers[key],
+                    value
+                );
+            }
+        }
+    }
+}
+
+pub fn show_env_var(name: &str, raw: bool, options: &StoreOptions) {
+    let Some((env_vars, shared_keys)) = load_merged_env_vars(options) else {
+        std::process::exit(1);
+    };
+    let value = if raw {
+        Ok(env_vars.get(name).and_then(value::as_env_string))
+    } else {
+        externkit::env::interpolate::resolve_one(
+            &env_vars,
+            name,
+            Some(externkit::env::secrets::registry()),
+            &shared_keys,
+        )
+    };
+    match value {
+        Ok(Some(value)) => println!("{}", value),
+        Err(e) => {
+            eprintln!("{}", e.to_string().red());
+            std::process::exit(1);
+        }
+        Ok(None) => {
+            eprintln!(
+                "{}",
+                format!("Environment variable '{}' does not exist.", name).red()
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Prints the keys added, changed and removed between two sets of variables
+/// and returns how many differ.
+fn print_changes<V: PartialEq>(
+    before: &std::collections::HashMap<String, V>,
+    after: &std::collections::HashMap<String, V>,
+) -> usize {
+    let keys: std::collections::BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = 0;
+    for key in keys {
+        match (before.get(key), after.get(key)) {
+            (None, Some(_)) => println!("{}", format!("+ {}", key).green()),
+            (Some(_), None) => println!("{}", format!("- {}", key).red()),
+            (Some(old), Some(new)) if old != new => println!("{}", format!("~ {}", key).yellow()),
+            _ => continue,
+        }
+        changes += 1;
+    }
+    if changes == 0 {
+        println!("No changes.");
+    }
+    changes
+}
+
+pub fn import_env_file(path: &str, mode: &str, dry_run: bool, options: &StoreOptions) {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("{}", format!("Failed to read {}: {}", path, e).red());
+            return;
+        }
+    };
+    let Some(mut store) = load_env_vars(options) else {
+        return;
+    };
+    let env_vars = store.vars().clone();
+
+    let lookup = |name: &str| {
+        env_vars
+            .get(name)
+            .and_then(value::as_env_string)
+            .or_else(|| std::env::var(name).ok())
+    };
+    let entries = match crate::backend::dotenv::parse(&content, &lookup) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("{}", format!("Failed to parse {}: {}", path, e).red());
+            return;
+        }
+    };
+
+    let (entries, empty): (Vec<_>, Vec<_>) = entries
+        .into_iter()
+        .partition(|(_, value)| !value.is_empty());
+    if !empty.is_empty() {
+        let keys: Vec<&str> = empty.iter().map(|(key, _)| key.as_str()).collect();
+        println!(
+            "{}",
+            format!("Skipping keys with empty values: {}", keys.join(", ")).yellow()
+        );
+    }
+
+    let mut updated = match mode {
+        "overwrite" => std::collections::HashMap::new(),
+        _ => env_vars.clone(),
+    };
+    for (key, value) in entries {
+        if mode == "skip-existing" && updated.contains_key(&key) {
+            continue;
+        }
+        // The parser has already expanded the file's own references, so
+        // every `$` left is literal and must not be expanded again.
+        updated.insert(key, Value::String(value.replace('$', "$$")));
+    }
+
+    if dry_run {
+        println!(
+            "{}",
+            format!("Dry run: importing {} ({} mode)", path, mode).cyan()
+        );
+        print_changes(&env_vars, &updated);
+        return;
+    }
+    let changes = print_changes(&env_vars, &updated);
+    store.clear();
+    for (key, value) in updated {
+        store.set(key, value);
+    }
+    if !save_env_vars(&mut store, "import") {
+        return;
+    }
+    println!(
+        "{}",
+        format!("Imported {} change(s) from {}", changes, path).green()
+    );
+}
+
+pub fn export_env_file(path: &str, dry_run: bool, options: &StoreOptions) {
+    let Some(env_vars) = load_exported_env_vars(options, false) else {
+        return;
+    };
+    let sorted: std::collections::BTreeMap<&String, &String> = env_vars.iter().collect();
+    let content: String = sorted
+        .iter()
+        .map(|(key, value)| format!("{}={}\n", key, crate::backend::dotenv::quote(value)))
+        .collect();
+
+    if path == "-" {
+        print!("{}", content);
+        return;
+    }
+    if dry_run {
+        let existing = std::fs::read_to_string(path).unwrap_or_default();
+        let existing = match crate::backend::dotenv::parse(&existing, &|_| None) {
+            Ok(entries) => entries.into_iter().collect(),
+            Err(e) => {
+                println!("{}", format!("Failed to parse {}: {}", path, e).red());
+                return;
+            }
+        };
+        println!("{}", format!("Dry run: exporting to {}", path).cyan());
+        print_changes(&existing, &env_vars);
+        return;
+    }
+    if let Err(e) = std::fs::write(path, content) {
+        println!("{}", format!("Failed to write {}: {}", path, e).red());
+        return;
+    }
+    println!(
+        "{}",
+        format!(
+            "Exported {} environment variable(s) to {}",
+            env_vars.len(),
+            path
+        )
+        .green()
+    );
+}
+
+pub fn check_env_vars(options: &StoreOptions) {
+    let schema = match Schema::load(&options.project_dir) {
+        Ok(Some(schema)) => schema,
+        Ok(None) => {
+            println!(
+                "{}",
+                format!(
+                    "No {} found in {}.",
+                    schema::SCHEMA_FILE,
+                    options.project_dir.display()
+                )
+                .yellow()
+            );
+            return;
+        }
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            std::process::exit(1);
+        }
+    };
+    let Some(env_vars) = load_resolved_env_vars(options) else {
+        std::process::exit(1);
+    };
+
+    match schema.validate(&env_vars) {
+        Ok(violations) if violations.is_empty() => println!(
+            "{}",
+            format!("✓ Environment variables match {}", schema::SCHEMA_FILE)
+                .green()
+                .bold()
+        ),
+        Ok(violations) => {
+            for violation in &violations {
+                println!(
+                    "{} {}",
+                    format!("✗ {}:", violation.key).red().bold(),
+                    violation.message
+                );
+            }
+            println!(
+                "{}",
+                format!("{} problem(s) found.", violations.len()).red()
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn format_age(modified: std::time::SystemTime) -> String {
+    let seconds = modified.elapsed().map(|age| age.as_secs()).unwrap_or(0);
+    match seconds {
+        0..=59 => format!("{}s ago", seconds),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+pub fn restore_env_vars(index: Option<usize>, list: bool, options: &StoreOptions) {
+    let result = options.resolve_profile().and_then(|profile| {
+        let lock = StoreLock::acquire(&options.project_dir)?;
+        Ok((profile, lock))
+    });
+    let (profile, _lock) = match result {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            return;
+        }
+    };
+
+    // Plain and encrypted copies rotate separately; present them as one
+    // timeline, newest first.
+    let plain_path = profiles::store_path(&options.project_dir, &profile);
+    let encrypted_path = profiles::encrypted_store_path(&options.project_dir, &profile);
+    let mut backups: Vec<(std::time::SystemTime, &std::path::Path, std::path::PathBuf)> =
+        [&plain_path, &encrypted_path]
+            .into_iter()
+            .flat_map(|path| {
+                storage::list_backups(&options.project_dir, path)
+                    .into_iter()
+                    .map(move |(_, backup)| {
+                        let modified = std::fs::metadata(&backup)
+                            .and_then(|metadata| metadata.modified())
+                            .unwrap_or(std::time::UNIX_EPOCH);
+                        (modified, path.as_path(), backup)
+                    })
+            })
+            .collect();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.0));
+
+    if backups.is_empty() {
+        println!(
+            "{}",
+            format!("No backups found for profile '{}'.", profile).yellow()
+        );
+        return;
+    }
+    if list {
+        for (number, (modified, original, _)) in backups.iter().enumerate() {
+            let encrypted = if *original == encrypted_path.as_path() {
+                " (encrypted)"
+            } else {
+                ""
+            };
+            println!("{}  {}{}", number + 1, format_age(*modified), encrypted);
+        }
+        return;
+    }
+
+    let index = index.unwrap_or(1);
+    let Some((modified, original, backup)) = index.checked_sub(1).and_then(|i| backups.get(i))
+    else {
+        println!(
+            "{}",
+            format!(
+                "Backup {} does not exist. Use `externkit env restore --list` to see available backups.",
+                index
+            )
+            .red()
+        );
+        return;
+    };
+    let result = std::fs::read(backup)
+        .and_then(|content| storage::replace(&options.project_dir, original, &content));
+    if let Err(e) = result {
+        println!("{}", format!("Failed to restore backup: {}", e).red());
+        return;
+    }
+    if *original == plain_path.as_path() {
+        remove_store_file(&options.project_dir, &encrypted_path);
+    } else {
+        purge_plaintext_store(&options.project_dir, &plain_path);
+    }
+    println!(
+        "{}",
+        format!(
+            "Restored profile '{}' from backup {} ({}).",
+            profile,
+            index,
+            format_age(*modified)
+        )
+        .green()
+    );
+}
+
+fn short_hash(hash: Option<&String>) -> String {
+    hash.map(|hash| hash.chars().take(8).collect())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+pub fn show_history(key: Option<&String>, options: &StoreOptions) {
+    let result = options.resolve_profile().and_then(|profile| {
+        let entries = audit::read(&options.project_dir)?;
+        Ok((profile, entries))
+    });
+    let (profile, entries) = match result {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            return;
+        }
+    };
+    let entries: Vec<&audit::Entry> = entries
+        .iter()
+        .filter(|entry| entry.profile == profile)
+        .filter(|entry| key.is_none_or(|key| &entry.key == key))
+        .collect();
+    if entries.is_empty() {
+        println!("{}", "No changes recorded.".yellow());
+        return;
+    }
+
+    let width = entries
+        .iter()
+        .map(|entry| entry.key.len())
+        .max()
+        .unwrap_or(0);
+    for entry in entries {
+        let timestamp = entry
+            .timestamp
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S");
+        println!(
+            "{:>4}  {}  {:<8}  {:<7}  {}  {} -> {}",
+            entry.id.to_string().bold(),
+            timestamp,
+            entry.user,
+            entry.action,
+            format!("{:<width$}", entry.key).cyan(),
+            short_hash(entry.old_hash.as_ref()),
+            short_hash(entry.new_hash.as_ref()),
+        );
+    }
+    println!(
+        "{}",
+        format!(
+            "The log only keeps hashes. `env revert` can restore a value only while one of the last {} backups of the profile still holds it.",
+            storage::MAX_BACKUPS
+        )
+        .dimmed()
+    );
+}
+
+/// Reads a store file from the backup rotation, decrypting it when it is an
+/// encrypted copy.
+fn read_backup(
+    path: &std::path::Path,
+    key_source: Option<&KeySource>,
+) -> externkit::env::Result<std::collections::HashMap<String, Value>> {
+    let content = std::fs::read_to_string(path)?;
+    let plaintext = match key_source {
+        Some(key_source) => crypto::decrypt_str(&content, key_source)?,
+        None => content.into_bytes(),
+    };
+    externkit::env::parse_store(path, &plaintext)
+}
+
+/// Looks through the backups of the target profile for a value of `key`
+/// whose hash is `hash`. The audit log only stores hashes, so values can be
+/// recovered only while a copy of the store containing them is still kept.
+fn find_backup_value(
+    key: &str,
+    hash: &str,
+    audit_key: &[u8],
+    store: &Store,
+    options: &StoreOptions,
+) -> externkit::env::Result<Option<Value>> {
+    let matching = |env_vars: std::collections::HashMap<String, Value>| {
+        env_vars
+            .get(key)
+            .filter(|value| audit::hash_value(audit_key, value) == hash)
+            .cloned()
+    };
+
+    let plain_path = profiles::store_path(store.project_dir(), store.profile());
+    for (_, backup) in storage::list_backups(store.project_dir(), &plain_path) {
+        if let Some(value) = read_backup(&backup, None).ok().and_then(matching) {
+            return Ok(Some(value));
+        }
+    }
+
+    let encrypted_path = profiles::encrypted_store_path(store.project_dir(), store.profile());
+    let backups = storage::list_backups(store.project_dir(), &encrypted_path);
+    if backups.is_empty() {
+        return Ok(None);
+    }
+    let key_source = match store.key_source() {
+        Some(key_source) => key_source.clone(),
+        None => options.key_source()?,
+    };
+    for (_, backup) in backups {
+        if let Some(value) = matching(read_backup(&backup, Some(&key_source))?) {
+            return Ok(Some(value));
+        }
+    }
+    Ok(None)
+}
+
+/// Sets `key` back to the value it had right after audit entry `entry_id`.
+pub fn revert_env_var(key: &str, entry_id: u64, options: &StoreOptions) {
+    let Some(mut store) = load_env_vars(options) else {
+        return;
+    };
+    let entries = match audit::read(store.project_dir()) {
+        Ok(entries) => entries,
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            return;
+        }
+    };
+    let Some(entry) = entries
+        .into_iter()
+        .find(|entry| entry.id == entry_id && entry.key == key && entry.profile == store.profile())
+    else {
+        println!(
+            "{}",
+            format!(
+                "Entry {} does not record a change to '{}' in profile '{}'. Use `externkit env history {}` to list its changes.",
+                entry_id,
+                key,
+                store.profile(),
+                key
+            )
+            .red()
+        );
+        return;
+    };
+
+    let audit_key = match audit::audit_key(store.project_dir()) {
+        Ok(audit_key) => audit_key,
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            return;
+        }
+    };
+    let current_hash = store
+        .get(key)
+        .map(|value| audit::hash_value(&audit_key, value));
+    if current_hash == entry.new_hash {
+        println!(
+            "{}",
+            format!(
+                "Environment variable '{}' already matches entry {}.",
+                key, entry_id
+            )
+            .yellow()
+        );
+        return;
+    }
+
+    match &entry.new_hash {
+        None => {
+            store.remove(key);
+        }
+        Some(hash) => match find_backup_value(key, hash, &audit_key, &store, options) {
+            Ok(Some(value)) => {
+                store.set(key, value);
+            }
+            Ok(None) => {
+                println!(
+                    "{}",
+                    format!(
+                        "The value recorded in entry {} is no longer in the backups of profile '{}'.",
+                        entry_id,
+                        store.profile()
+                    )
+                    .red()
+                );
+                return;
+            }
+            Err(e) => {
+                println!("{}", e.to_string().red());
+                return;
+            }
+        },
+    }
+    if !save_env_vars(&mut store, "revert") {
+        return;
+    }
+    println!(
+        "{}",
+        format!(
+            "Reverted environment variable '{}' to entry {}.",
+            key, entry_id
+        )
+        .green()
+    );
+}
+
+/// Problems `doctor` found in one file of the project.
+struct Diagnosis {
+    path: std::path::PathBuf,
+    fixes: Vec<String>,
+    error: Option<String>,
+}
+
+fn diagnose_profile_config(
+    project_dir: &std::path::Path,
+    dry_run: bool,
+) -> (ProfileConfig, Diagnosis) {
+    let path = project_dir.join(profiles::PROFILES_FILE);
+    let mut diagnosis = Diagnosis {
+        path: path.clone(),
+        fixes: Vec::new(),
+        error: None,
+    };
+    let mut config = match ProfileConfig::load(project_dir) {
+        Ok(config) => config,
+        Err(e) => {
+            let repaired = std::fs::read_to_string(&path).and_then(|content| {
+                let (value, fixes) = repair::repair_json(&content)?;
+                diagnosis.fixes = fixes;
+                if value.is_null() {
+                    return Ok(ProfileConfig::default());
+                }
+                serde_json::from_value(value)
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+            });
+            match repaired {
+                Ok(config) => config,
+                Err(_) => {
+                    diagnosis.error = Some(e.to_string());
+                    return (ProfileConfig::default(), diagnosis);
+                }
+            }
+        }
+    };
+
+    if let Some(active) = config.active.clone() {
+        if !config.exists(&active) {
+            diagnosis.fixes.push(format!(
+                "Reset the active profile, '{}' does not exist",
+                active
+            ));
+            config.active = None;
+        }
+    }
+    let names = config.names();
+    for (name, entry) in config.profiles.iter_mut() {
+        if let Some(base) = entry.extends.clone() {
+            if !names.contains(&base) {
+                diagnosis.fixes.push(format!(
+                    "Removed the missing base profile '{}' of '{}'",
+                    base, name
+                ));
+                entry.extends = None;
+            }
+        }
+    }
+
+    if !diagnosis.fixes.is_empty() && !dry_run {
+        let result = serde_json::to_string_pretty(&config)
+            .map_err(std::io::Error::other)
+            .and_then(|content| storage::replace(project_dir, &path, content.as_bytes()));
+        if let Err(e) = result {
+            diagnosis.error = Some(format!("Failed to save the repaired file: {}", e));
+        }
+    }
+    (config, diagnosis)
+}
+
+fn diagnose_profile(project_dir: &std::path::Path, profile: &str, dry_run: bool) -> Diagnosis {
+    let path = profiles::store_path(project_dir, profile);
+    let encrypted_path = profiles::encrypted_store_path(project_dir, profile);
+    let mut diagnosis = Diagnosis {
+        path: path.clone(),
+        fixes: Vec::new(),
+        error: None,
+    };
+
+    if encrypted_path.exists() {
+        diagnosis.path = encrypted_path.clone();
+        if path.exists() {
+            diagnosis.fixes.push(format!(
+                "Deleted the ignored plaintext copy {} and its backups",
+                path.display()
+            ));
+            if !dry_run {
+                purge_plaintext_store(project_dir, &path);
+            }
+        }
+        // The ciphertext cannot be repaired, only checked for being intact.
+        let readable = std::fs::read_to_string(&encrypted_path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| {
+                serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = readable {
+            diagnosis.error = Some(format!(
+                "The encrypted store is damaged ({}). Use `externkit env restore` to go back to a backup.",
+                e
+            ));
+        }
+        return diagnosis;
+    }
+
+    let content = match std::fs::read(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            diagnosis
+                .fixes
+                .push("Created the missing store file".to_string());
+            if !dry_run {
+                if let Err(e) = storage::write_atomic(&path, b"{\n}") {
+                    diagnosis.error = Some(e.to_string());
+                }
+            }
+            return diagnosis;
+        }
+        Err(e) => {
+            diagnosis.error = Some(e.to_string());
+            return diagnosis;
+        }
+    };
+    repair_plaintext_store(project_dir, &content, diagnosis, dry_run)
+}
+
+/// Checks the committed shared variables. Unlike a profile's store the file
+/// is optional, so a missing one is left alone.
+fn diagnose_shared(project_dir: &std::path::Path, dry_run: bool) -> Option<Diagnosis> {
+    let path = profiles::shared_path(project_dir);
+    let diagnosis = Diagnosis {
+        path: path.clone(),
+        fixes: Vec::new(),
+        error: None,
+    };
+    match std::fs::read(&path) {
+        Ok(content) => Some(repair_plaintext_store(
+            project_dir,
+            &content,
+            diagnosis,
+            dry_run,
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => Some(Diagnosis {
+            error: Some(e.to_string()),
+            ..diagnosis
+        }),
+    }
+}
+
+/// Repairs `content`, read from `diagnosis.path`, if it does not parse.
+fn repair_plaintext_store(
+    project_dir: &std::path::Path,
+    content: &[u8],
+    mut diagnosis: Diagnosis,
+    dry_run: bool,
+) -> Diagnosis {
+    let path = diagnosis.path.clone();
+    if externkit::env::parse_store(&path, content).is_ok() {
+        return diagnosis;
+    }
+
+    let repaired = match repair::repair_store(&String::from_utf8_lossy(content)) {
+        Ok(repaired) => repaired,
+        Err(e) => {
+            diagnosis.error = Some(format!(
+                "{} Use `externkit env restore` to go back to a backup.",
+                e
+            ));
+            return diagnosis;
+        }
+    };
+    diagnosis.fixes = repaired.fixes;
+    if !dry_run {
+        let content = serde_json::to_string_pretty(&repaired.env_vars).unwrap();
+        if let Err(e) = storage::replace(project_dir, &path, content.as_bytes()) {
+            diagnosis.error = Some(format!("Failed to save the repaired file: {}", e));
+        }
+    }
+    diagnosis
+}
+
+/// Removes temporary files left behind by writes that were interrupted
+/// before the rename.
+/// Projects created before the shared layer ignore every file, so the
+/// shared variables would never be committed.
+fn diagnose_gitignore(project_dir: &std::path::Path, dry_run: bool) -> Option<Diagnosis> {
+    let path = project_dir.join(".gitignore");
+    match crate::backend::utils::update_gitignore(project_dir, dry_run) {
+        Ok(added) if added.is_empty() => None,
+        Ok(added) => Some(Diagnosis {
+            path,
+            fixes: added
+                .iter()
+                .map(|rule| format!("Added the rule '{}'", rule))
+                .collect(),
+            error: None,
+        }),
+        Err(e) => Some(Diagnosis {
+            path,
+            fixes: Vec::new(),
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+fn diagnose_temp_files(project_dir: &std::path::Path, dry_run: bool) -> Vec<Diagnosis> {
+    [
+        project_dir.to_path_buf(),
+        project_dir.join(profiles::PROFILES_DIR),
+    ]
+    .iter()
+    .filter_map(|dir| std::fs::read_dir(dir).ok())
+    .flatten()
+    .filter_map(|entry| entry.ok())
+    .map(|entry| entry.path())
+    .filter(|path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy())
+            .is_some_and(|name| name.starts_with('.') && name.contains(".tmp-"))
+    })
+    .map(|path| {
+        let error = if dry_run {
+            None
+        } else {
+            std::fs::remove_file(&path).err().map(|e| e.to_string())
+        };
+        Diagnosis {
+            path,
+            fixes: vec!["Removed a leftover temporary file".to_string()],
+            error,
+        }
+    })
+    .collect()
+}
+
+pub fn doctor_env_vars(dry_run: bool, options: &StoreOptions) {
+    let project_dir = &options.project_dir;
+    let _lock = match StoreLock::acquire(project_dir) {
+        Ok(lock) => lock,
+        Err(e) => {
+            println!("{}", e.to_string().red());
+            std::process::exit(1);
+        }
+    };
+
+    let mut diagnoses = diagnose_temp_files(project_dir, dry_run);
+    let (config, diagnosis) = diagnose_profile_config(project_dir, dry_run);
+    diagnoses.push(diagnosis);
+    diagnoses.extend(diagnose_shared(project_dir, dry_run));
+    diagnoses.extend(diagnose_gitignore(project_dir, dry_run));
+    for profile in config.names() {
+        diagnoses.push(diagnose_profile(project_dir, &profile, dry_run));
+    }
+
+    if dry_run {
+        println!("{}", "Dry run: no files will be changed".cyan());
+    }
+    let mut repaired = 0;
+    let mut failed = 0;
+    for diagnosis in &diagnoses {
+        if diagnosis.fixes.is_empty() && diagnosis.error.is_none() {
+            continue;
+        }
+        println!("{}", diagnosis.path.display().to_string().bold());
+        for fix in &diagnosis.fixes {
+            println!("  {}", format!("~ {}", fix).yellow());
+        }
+        match &diagnosis.error {
+            Some(error) => {
+                println!("  {}", format!("✗ {}", error).red());
+                failed += 1;
+            }
+            None => repaired += 1,
+        }
+    }
+
+    if repaired == 0 && failed == 0 {
+        println!("{}", "✓ No problems found.".green().bold());
+        return;
+    }
+    if repaired > 0 && !dry_run {
+        println!(
+            "{}",
+            format!(
+                "Repaired {} file(s). The previous versions are in {}.",
+                repaired,
+                project_dir.join(storage::BACKUP_DIR).display()
+            )
+            .green()
+        );
+    }
+    if failed > 0 {
+        println!(
+            "{}",
+            format!("{} file(s) could not be repaired.", failed).red()
+        );
+        std::process::exit(1);
+    }
+}
@@ -1,3 +1,7 @@
+pub mod dotenv;
 pub mod env;
 pub mod python_tools;
+pub mod run;
+pub mod shell;
+pub mod sync;
 pub mod utils;
@@ -0,0 +1,60 @@
+use colored::Colorize;
+
+use crate::backend::env::StoreOptions;
+
+pub struct RunOptions {
+    pub override_os: bool,
+    pub clear_env: bool,
+}
+
+/// Runs `command` with the stored variables merged into its environment and
+/// exits with the child's exit code.
+pub fn run_command(command: &[String], run_options: &RunOptions, options: &StoreOptions) -> ! {
+    let Some(env_vars) = crate::backend::env::load_exported_env_vars(options, true) else {
+        std::process::exit(1);
+    };
+    let (program, args) = command.split_first().expect("required");
+
+    let mut child = std::process::Command::new(program);
+    child.args(args);
+    if run_options.clear_env {
+        child.env_clear();
+    }
+    for (key, value) in env_vars {
+        if run_options.clear_env || run_options.override_os || std::env::var_os(&key).is_none() {
+            child.env(key, value);
+        }
+    }
+
+    exec(child, program)
+}
+
+// Replacing the current process hands signals and the exit status straight
+// to the command, so nothing needs to be forwarded by hand.
+#[cfg(unix)]
+fn exec(mut child: std::process::Command, program: &str) -> ! {
+    use std::os::unix::process::CommandExt;
+
+    let e = child.exec();
+    eprintln!("{}", format!("Failed to run '{}': {}", program, e).red());
+    std::process::exit(if e.kind() == std::io::ErrorKind::NotFound {
+        127
+    } else {
+        126
+    });
+}
+
+#[cfg(not(unix))]
+fn exec(mut child: std::process::Command, program: &str) -> ! {
+    match child.status() {
+        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
+        Err(e) => {
+            eprintln!("{}", format!("Failed to run '{}': {}", program, e).red());
+            std::process::exit(if e.kind() == std::io::ErrorKind::NotFound {
+                127
+            } else {
+                126
+            });
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::collections::{BTreeMap, HashSet};
+
+use colored::Colorize;
+use externkit::env::{interpolate, value};
+
+use crate::backend::env::StoreOptions;
+
+/// Project directory whose variables the shell hook has exported.
+const HOOK_DIR_VAR: &str = "EXTERNKIT_HOOK_DIR";
+/// `:`-separated names the shell hook has exported, unset again on leaving.
+const HOOK_VARS_VAR: &str = "EXTERNKIT_HOOK_VARS";
+
+#[derive(Clone, Copy)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl Shell {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bash" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "powershell" => Some(Shell::Powershell),
+            _ => None,
+        }
+    }
+
+    /// A statement setting `key` to `value` in the current shell session.
+    fn export(self, key: &str, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export {}={};", key, quote_posix(value)),
+            Shell::Fish => format!("set -gx {} {};", key, quote_fish(value)),
+            Shell::Powershell => format!("$env:{} = {};", key, quote_powershell(value)),
+        }
+    }
+
+    fn unset(self, key: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("unset {};", key),
+            Shell::Fish => format!("set -e {};", key),
+            Shell::Powershell => {
+                format!("Remove-Item Env:{} -ErrorAction SilentlyContinue;", key)
+            }
+        }
+    }
+}
+
+/// Single quotes keep everything literal in POSIX shells; a quote itself
+/// has to close the string, be escaped and reopen it.
+fn quote_posix(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Inside single quotes fish only interprets `\\` and `\'`.
+fn quote_fish(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Powershell doubles single quotes inside single-quoted strings.
+fn quote_powershell(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Variables that change how the shell or the dynamic linker behaves, such
+/// as `PROMPT_COMMAND` running code at the next prompt. The hook never sets
+/// them, whatever layer they come from.
+const SENSITIVE_NAMES: &[&str] = &[
+    "BASH_ENV",
+    "BASHOPTS",
+    "CDPATH",
+    "ENV",
+    "FPATH",
+    "GLOBIGNORE",
+    "HISTFILE",
+    "IFS",
+    "PATH",
+    "PROMPT",
+    "PROMPT_COMMAND",
+    "PS0",
+    "PS1",
+    "PS2",
+    "PS3",
+    "PS4",
+    "RPROMPT",
+    "RPS1",
+    "SHELLOPTS",
+    "ZDOTDIR",
+    "chpwd_functions",
+    "precmd_functions",
+    "preexec_functions",
+];
+
+fn is_sensitive(key: &str) -> bool {
+    SENSITIVE_NAMES.contains(&key)
+        || key.starts_with("LD_")
+        || key.starts_with("DYLD_")
+        || key.starts_with("EXTERNKIT_")
+}
+
+/// Only names every supported shell accepts as a variable are exported.
+fn is_shell_name(key: &str) -> bool {
+    !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Prints the hook that loads the variables of the project the shell is in
+/// whenever the working directory changes.
+pub fn print_hook(shell: Shell) {
+    let exe = std::env::current_exe()
+        .ok()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|| "externkit".to_string());
+    let script = match shell {
+        Shell::Bash => format!(
+            r#"_externkit_hook() {{
+  local previous_exit_status=$?
+  if [[ "${{_EXTERNKIT_PWD:-}}" != "$PWD" ]]; then
+    _EXTERNKIT_PWD="$PWD"
+    eval "$({exe} env print --shell bash --hook)"
+  fi
+  return $previous_exit_status
+}}
+if [[ ";${{PROMPT_COMMAND[*]:-}};" != *";_externkit_hook;"* ]]; then
+  PROMPT_COMMAND="_externkit_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi"#,
+            exe = quote_posix(&exe)
+        ),
+        Shell::Zsh => format!(
+            r#"_externkit_hook() {{
+  eval "$({exe} env print --shell zsh --hook)"
+}}
+typeset -ag chpwd_functions
+if (( ! ${{chpwd_functions[(I)_externkit_hook]}} )); then
+  chpwd_functions=(_externkit_hook $chpwd_functions)
+fi
+_externkit_hook"#,
+            exe = quote_posix(&exe)
+        ),
+        Shell::Fish => format!(
+            r#"function _externkit_hook --on-variable PWD
+    {exe} env print --shell fish --hook | source
+end
+_externkit_hook"#,
+            exe = quote_fish(&exe)
+        ),
+        Shell::Powershell => unreachable!("`shell-hook` only accepts bash, zsh and fish"),
+    };
+    println!("{}", script);
+}
+
+/// Prints statements exporting the selected profile, for `eval`. Errors go
+/// to stderr so they are never evaluated.
+pub fn print_env(shell: Shell, options: &StoreOptions) {
+    let env_vars = match options
+        .open_store(false, true)
+        .and_then(|store| store.resolved_secrets(externkit::env::secrets::registry()))
+    {
+        Ok(env_vars) => env_vars,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red());
+            std::process::exit(1);
+        }
+    };
+    let env_vars: BTreeMap<String, String> = env_vars.into_iter().collect();
+    for (key, value) in &env_vars {
+        if is_shell_name(key) {
+            println!("{}", shell.export(key, value));
+        } else {
+            eprintln!(
+                "{}",
+                format!("Skipping '{}': not a valid shell variable name.", key).yellow()
+            );
+        }
+    }
+}
+
+/// What the shell hook evaluates on every directory change: nothing while
+/// the shell stays inside the same project, otherwise statements unsetting
+/// what was exported for the previous project and exporting the variables
+/// of the new one. Variables already set in the OS environment are left
+/// alone, as with `externkit run`, and shell-sensitive names are never set.
+/// The committed shared file is only loaded, and secret references only
+/// resolved, once the project has been allowed with `externkit env allow`.
+/// Encrypted profiles are only loaded when `EXTERNKIT_PASSPHRASE` or
+/// `EXTERNKIT_KEYFILE` is set; the hook never prompts.
+pub fn print_hook_env(shell: Shell, options: Option<StoreOptions>) {
+    let loaded_dir = std::env::var(HOOK_DIR_VAR).ok();
+    let project_dir = options
+        .as_ref()
+        .map(|options| options.project_dir.display().to_string());
+    if loaded_dir.is_some() && loaded_dir == project_dir {
+        return;
+    }
+
+    let loaded_vars: HashSet<String> = std::env::var(HOOK_VARS_VAR)
+        .unwrap_or_default()
+        .split(':')
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect();
+    let mut loaded: Vec<&String> = loaded_vars.iter().collect();
+    loaded.sort();
+    for key in loaded {
+        println!("{}", shell.unset(key));
+    }
+    println!("{}", shell.unset(HOOK_DIR_VAR));
+    println!("{}", shell.unset(HOOK_VARS_VAR));
+    if let Some(dir) = &loaded_dir {
+        eprintln!("externkit: unloaded {}", dir);
+    }
+
+    let Some(mut options) = options else {
+        return;
+    };
+    options.prompt = false;
+    let trusted = externkit::env::trust::is_trusted(&options.project_dir);
+    if !trusted && externkit::env::profiles::shared_path(&options.project_dir).exists() {
+        eprintln!(
+            "externkit: not loading the shared variables of {} until it is trusted with `externkit env allow`",
+            options.project_dir.display()
+        );
+    }
+    let secrets = externkit::env::secrets::registry();
+    let env_vars = match options.open_store(false, true).and_then(|store| {
+        if trusted {
+            store.resolved_secrets(secrets)
+        } else {
+            // Shared values may still be expanded into local ones, but are
+            // not exported themselves, and nothing is resolved as a secret:
+            // that would run `secret+cmd:` references of a directory that was
+            // merely entered.
+            let (merged, shared_keys) = store.merged_with_shared_keys()?;
+            let mut env_vars = interpolate::Interpolator::new(&value::flatten(&merged)).resolve_all()?;
+            env_vars.retain(|key, value| {
+                if shared_keys.contains(key) {
+                    return false;
+                }
+                let reference = secrets.is_reference(value);
+                if reference {
+                    eprintln!(
+                        "externkit: not exporting {}, secret references are only resolved in trusted projects",
+                        key
+                    );
+                }
+                !reference
+            });
+            Ok(env_vars)
+        }
+    }) {
+        Ok(env_vars) => env_vars,
+        Err(e) => {
+            eprintln!("externkit: {}", e);
+            return;
+        }
+    };
+    let env_vars: BTreeMap<String, String> = env_vars
+        .into_iter()
+        .filter(|(key, _)| is_shell_name(key))
+        .filter(|(key, _)| {
+            let sensitive = is_sensitive(key);
+            if sensitive {
+                eprintln!("externkit: not exporting {}, the hook never sets it", key);
+            }
+            !sensitive
+        })
+        .filter(|(key, _)| loaded_vars.contains(key) || std::env::var_os(key).is_none())
+        .collect();
+    for (key, value) in &env_vars {
+        println!("{}", shell.export(key, value));
+    }
+    let names: Vec<&str> = env_vars.keys().map(String::as_str).collect();
+    let project_dir = project_dir.expect("options are set");
+    println!("{}", shell.export(HOOK_DIR_VAR, &project_dir));
+    println!("{}", shell.export(HOOK_VARS_VAR, &names.join(":")));
+    eprintln!(
+        "externkit: loaded {} variable(s) from {}",
+        names.len(),
+        project_dir
+    );
+}
+
+/// Lets the shell hook load the project's shared file and resolve the
+/// secret references of its profiles, with those files as they are now.
+/// Any later change made other than through externkit withdraws the trust
+/// again.
+pub fn allow_project(project_dir: &std::path::Path) {
+    if let Err(e) = externkit::env::trust::allow(project_dir) {
+        println!("{}", format!("Failed to trust the project: {}", e).red());
+        std::process::exit(1);
+    }
+    println!(
+        "{}",
+        format!(
+            "Trusted {}. The shell hook now loads its shared variables and secrets.",
+            project_dir.display()
+        )
+        .green()
+    );
+}
+
+pub fn deny_project(project_dir: &std::path::Path) {
+    match externkit::env::trust::deny(project_dir) {
+        Ok(true) => println!(
+            "{}",
+            format!("{} is no longer trusted.", project_dir.display()).green()
+        ),
+        Ok(false) => println!("{}", "The project was not trusted.".yellow()),
+        Err(e) => {
+            println!(
+                "{}",
+                format!("Failed to update the trusted projects: {}", e).red()
+            );
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,421 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use colored::Colorize;
+use externkit::env::profiles::{self, ProfileConfig};
+use externkit::env::storage::{self, StoreLock};
+use externkit::env::{interpolate, value, Store, Value};
+
+use crate::backend::env::{check_references, mask, save_env_vars, StoreOptions};
+
+/// One side of `env diff` or `env sync`.
+enum Source {
+    /// The environment of the externkit process itself.
+    Os,
+    Profile(String),
+    /// A store file such as `environment_variables.json`.
+    Json(PathBuf),
+    Dotenv(PathBuf),
+}
+
+impl Source {
+    /// `@os`, then a profile of the project, then a file. `.json` files are
+    /// read as stores, anything else as dotenv. A profile's own store file
+    /// is the profile, so syncing into it goes through `Store`.
+    fn parse(spec: &str, options: &StoreOptions) -> Result<Self, String> {
+        if spec == "@os" {
+            return Ok(Source::Os);
+        }
+        let config = ProfileConfig::load(&options.project_dir).map_err(|e| e.to_string())?;
+        if config.exists(spec) {
+            return Ok(Source::Profile(spec.to_string()));
+        }
+        let path = PathBuf::from(spec);
+        if !path.is_file() {
+            return Err(format!(
+                "'{}' is neither a profile nor a file. Use `@os` for the process environment.",
+                spec
+            ));
+        }
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            let canonical = path.canonicalize().ok();
+            let profile = config.names().into_iter().find(|name| {
+                let store_path = profiles::store_path(&options.project_dir, name);
+                store_path.canonicalize().ok() == canonical
+            });
+            Ok(profile.map_or(Source::Json(path), Source::Profile))
+        } else {
+            Ok(Source::Dotenv(path))
+        }
+    }
+
+    /// Whether the side keeps typed and nested values, so references can be
+    /// copied into it as written.
+    fn is_store(&self) -> bool {
+        matches!(self, Source::Profile(_) | Source::Json(_))
+    }
+
+    /// The variables as stored. Profiles and store files keep references,
+    /// types and nested objects; the other sides only have strings.
+    fn load(&self, options: &StoreOptions) -> Result<HashMap<String, Value>, String> {
+        let strings = |vars: HashMap<String, String>| {
+            vars.into_iter()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect()
+        };
+        match self {
+            Source::Os => Ok(strings(std::env::vars().collect())),
+            Source::Profile(profile) => profile_options(profile, options)
+                .open_store(false, true)
+                .and_then(|store| store.merged())
+                .map_err(|e| e.to_string()),
+            Source::Json(path) => read_json(path),
+            Source::Dotenv(path) => Ok(strings(read_dotenv(path)?.into_iter().collect())),
+        }
+    }
+}
+
+/// The side `env sync` writes to, opened under the project lock. The lock is
+/// held from reading the current variables until the changes are written,
+/// so nothing written in between is overwritten unseen.
+enum Target {
+    /// The profile's store, which holds the lock itself and records the
+    /// changes in the backups and the audit log.
+    Profile(Box<Store>),
+    Json {
+        path: PathBuf,
+        _lock: StoreLock,
+    },
+    Dotenv {
+        path: PathBuf,
+        _lock: StoreLock,
+    },
+}
+
+impl Target {
+    fn open(source: Source, options: &StoreOptions) -> Result<Self, String> {
+        let lock = || StoreLock::acquire(&options.project_dir).map_err(|e| e.to_string());
+        match source {
+            Source::Os => Err(
+                "The process environment cannot be changed. Use `externkit env print` to export variables into a shell."
+                    .to_string(),
+            ),
+            Source::Profile(profile) => profile_options(&profile, options)
+                .open_store(true, true)
+                .map(|store| Target::Profile(Box::new(store)))
+                .map_err(|e| e.to_string()),
+            Source::Json(path) => Ok(Target::Json { path, _lock: lock()? }),
+            Source::Dotenv(path) => Ok(Target::Dotenv { path, _lock: lock()? }),
+        }
+    }
+
+    fn is_store(&self) -> bool {
+        matches!(self, Target::Profile(_) | Target::Json { .. })
+    }
+
+    /// The variables as stored, like `Source::load`.
+    fn load(&self) -> Result<HashMap<String, Value>, String> {
+        match self {
+            Target::Profile(store) => store.merged().map_err(|e| e.to_string()),
+            Target::Json { path, .. } => read_json(path),
+            Target::Dotenv { path, .. } => Ok(read_dotenv(path)?
+                .into_iter()
+                .map(|(key, value)| (key, Value::String(value)))
+                .collect()),
+        }
+    }
+
+    /// Applies `changes`, where `None` removes the key. Returns the keys that
+    /// could not be removed because the profile only inherits them.
+    fn apply(&mut self, changes: &[(&String, Option<&Value>)]) -> Result<Vec<String>, String> {
+        let mut inherited = Vec::new();
+        match self {
+            Target::Profile(store) => {
+                for (key, change) in changes {
+                    match change {
+                        Some(value) => {
+                            store.set(key.as_str(), (*value).clone());
+                        }
+                        None if store.remove(key).is_none() => inherited.push(key.to_string()),
+                        None => {}
+                    }
+                }
+                let set = changes
+                    .iter()
+                    .filter(|(_, change)| change.is_some())
+                    .map(|(key, _)| key.as_str());
+                if !check_references(store, set) || !save_env_vars(store, "sync") {
+                    return Err("Nothing was synced.".to_string());
+                }
+            }
+            Target::Json { path, .. } => {
+                let mut vars = read_json(path)?;
+                for (key, change) in changes {
+                    match change {
+                        Some(value) => {
+                            vars.insert(key.to_string(), (*value).clone());
+                        }
+                        None => {
+                            vars.remove(key.as_str());
+                        }
+                    }
+                }
+                let content = serde_json::to_string_pretty(&vars).unwrap();
+                storage::write_atomic(path, content.as_bytes())
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+            Target::Dotenv { path, .. } => {
+                let mut entries = read_dotenv(path)?;
+                for (key, change) in changes {
+                    let existing = entries.iter().position(|(name, _)| name == *key);
+                    match (change, existing) {
+                        (Some(value), Some(index)) => entries[index].1 = display(value),
+                        (Some(value), None) => entries.push((key.to_string(), display(value))),
+                        (None, Some(index)) => {
+                            entries.remove(index);
+                        }
+                        (None, None) => {}
+                    }
+                }
+                let content: String = entries
+                    .iter()
+                    .map(|(key, value)| {
+                        format!("{}={}\n", key, crate::backend::dotenv::quote(value))
+                    })
+                    .collect();
+                storage::write_atomic(path, content.as_bytes())
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            }
+        }
+        Ok(inherited)
+    }
+}
+
+fn profile_options(profile: &str, options: &StoreOptions) -> StoreOptions {
+    StoreOptions {
+        project_dir: options.project_dir.clone(),
+        keyfile: options.keyfile.clone(),
+        profile: Some(profile.to_string()),
+        prompt: options.prompt,
+    }
+}
+
+fn read_json(path: &std::path::Path) -> Result<HashMap<String, Value>, String> {
+    let content =
+        std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    externkit::env::parse_store(path, &content).map_err(|e| e.to_string())
+}
+
+/// Entries of a dotenv file in file order, references expanded against the
+/// OS environment.
+fn read_dotenv(path: &std::path::Path) -> Result<Vec<(String, String)>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    crate::backend::dotenv::parse(&content, &|name| std::env::var(name).ok())
+        .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// The variables of one side, by name.
+type Vars = HashMap<String, Value>;
+
+/// Variables of a side that is a store as a process would see them:
+/// flattened, with `${NAME}` references expanded. Secret references are kept
+/// as written. Other sides only have strings and are returned as they are.
+fn resolve(vars: Vars, is_store: bool) -> Result<Vars, String> {
+    if !is_store {
+        return Ok(vars);
+    }
+    let resolved = interpolate::Interpolator::new(&value::flatten(&vars))
+        .resolve_all()
+        .map_err(|e| e.to_string())?;
+    Ok(resolved
+        .into_iter()
+        .map(|(key, value)| (key, Value::String(value)))
+        .collect())
+}
+
+/// Two profiles or store files are compared as stored, so syncing between
+/// them copies references, types and nested objects unchanged. Otherwise
+/// both sides are compared as a process would see them.
+fn comparable(
+    (a_vars, a_store): (Vars, bool),
+    (b_vars, b_store): (Vars, bool),
+) -> Result<(Vars, Vars), String> {
+    if a_store && b_store {
+        return Ok((a_vars, b_vars));
+    }
+    Ok((resolve(a_vars, a_store)?, resolve(b_vars, b_store)?))
+}
+
+/// Parses and loads both sides of `env diff`.
+fn open(a: &str, b: &str, options: &StoreOptions) -> Result<(Vars, Vars), String> {
+    let a = Source::parse(a, options)?;
+    let b = Source::parse(b, options)?;
+    comparable(
+        (a.load(options)?, a.is_store()),
+        (b.load(options)?, b.is_store()),
+    )
+}
+
+/// Parses and loads both sides of `env sync`, taking the project lock for
+/// the target before reading it.
+fn open_for_sync(
+    from: &str,
+    to: &str,
+    options: &StoreOptions,
+) -> Result<(Vars, Target, Vars), String> {
+    let from = Source::parse(from, options)?;
+    let target = Target::open(Source::parse(to, options)?, options)?;
+    let (from_vars, to_vars) = comparable(
+        (from.load(options)?, from.is_store()),
+        (target.load()?, target.is_store()),
+    )?;
+    Ok((from_vars, target, to_vars))
+}
+
+/// A value as it is shown and written to dotenv files: strings as they are,
+/// anything else as JSON.
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Prints what turns `before` into `after` and returns those changes,
+/// sorted by key; `None` removes a key. Keys only `before` has are left out
+/// unless `removals` is set.
+fn print_diff<'a>(
+    before: &'a Vars,
+    after: &'a Vars,
+    reveal: bool,
+    removals: bool,
+) -> Vec<(&'a String, Option<&'a Value>)> {
+    let show = |value: &Value| {
+        let value = display(value);
+        if reveal {
+            value
+        } else {
+            mask(&value)
+        }
+    };
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let mut changes = Vec::new();
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for key in keys {
+        match (before.get(key), after.get(key)) {
+            (None, Some(new)) => {
+                println!("{}", format!("+ {}={}", key, show(new)).green());
+                added += 1;
+            }
+            (Some(old), None) if removals => {
+                println!("{}", format!("- {}={}", key, show(old)).red());
+                removed += 1;
+            }
+            (Some(old), Some(new)) if old != new => {
+                println!(
+                    "{}",
+                    format!("~ {}={} -> {}", key, show(old), show(new)).yellow()
+                );
+                changed += 1;
+            }
+            _ => continue,
+        }
+        changes.push((key, after.get(key)));
+    }
+    if changes.is_empty() {
+        println!("No differences.");
+    } else {
+        println!("{} added, {} removed, {} changed", added, removed, changed);
+    }
+    changes
+}
+
+/// Shows the keys added, removed and changed going from `a` to `b`.
+pub fn diff_env_vars(a: &str, b: &str, reveal: bool, options: &StoreOptions) {
+    let (a_vars, b_vars) = match open(a, b, options) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("{}", e.red());
+            std::process::exit(1);
+        }
+    };
+    println!("{}", format!("--- {}\n+++ {}", a, b).bold());
+    print_diff(&a_vars, &b_vars, reveal, true);
+}
+
+/// Copies the keys that are missing or different in `to` from `from`, and
+/// with `prune` removes the keys `from` does not have. Asks before writing
+/// unless `yes` is given.
+pub fn sync_env_vars(
+    from: &str,
+    to: &str,
+    prune: bool,
+    yes: bool,
+    reveal: bool,
+    options: &StoreOptions,
+) {
+    let (from_vars, mut target, to_vars) = match open_for_sync(from, to, options) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            println!("{}", e.red());
+            std::process::exit(1);
+        }
+    };
+
+    println!("{}", format!("--- {}\n+++ {}", to, from).bold());
+    let changes = print_diff(&to_vars, &from_vars, reveal, prune);
+    if changes.is_empty() {
+        return;
+    }
+    if !yes
+        && !confirm(&format!(
+            "Apply {} change(s) to {}? [y/N] ",
+            changes.len(),
+            to
+        ))
+    {
+        println!("{}", "Sync cancelled.".yellow());
+        return;
+    }
+
+    match target.apply(&changes) {
+        Ok(inherited) => {
+            if !inherited.is_empty() {
+                println!(
+                    "{}",
+                    format!(
+                        "Not removed, inherited from another layer: {}",
+                        inherited.join(", ")
+                    )
+                    .yellow()
+                );
+            }
+            println!(
+                "{}",
+                format!("Synced {} change(s) from {} to {}", changes.len(), from, to).green()
+            );
+        }
+        Err(e) => {
+            println!("{}", e.red());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn confirm(prompt: &str) -> bool {
+    use std::io::Write;
+
+    print!("{}", prompt);
+    if std::io::stdout().flush().is_err() {
+        return false;
+    }
+    let mut answer = String::new();
+    match std::io::stdin().read_line(&mut answer) {
+        Ok(_) => matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"),
+        Err(_) => false,
+    }
+}
@@ -1,11 +1,134 @@
-pub fn init_project() {
-    let project_path = std::path::PathBuf::from("./.externkit");
+use colored::Colorize;
+
+/// The rules of `.externkit/.gitignore`: everything is ignored except the
+/// shared variables and the schema, which are meant to be committed.
+const GITIGNORE_RULES: [&str; 4] = [
+    "*",
+    "!.gitignore",
+    "!environment.shared.json",
+    "!env.schema.json",
+];
+
+pub fn init_project(project_path: &std::path::Path) {
     if !project_path.exists() {
-        std::fs::create_dir_all(&project_path).expect("Failed to create project directory");
-        std::fs::write(project_path.join(".gitignore"), "*")
-            .expect("Failed to create gitignore file");
+        std::fs::create_dir_all(project_path).expect("Failed to create project directory");
+        std::fs::write(
+            project_path.join(".gitignore"),
+            GITIGNORE_RULES.join("\n") + "\n",
+        )
+        .expect("Failed to create gitignore file");
 
         std::fs::write(project_path.join("environment_variables.json"), "{\n}")
             .expect("Failed to create environment_variables.json file");
+        return;
+    }
+    // Projects created before the shared layer ignore every file.
+    match update_gitignore(project_path, false) {
+        Ok(added) if added.is_empty() => {}
+        Ok(added) => println!(
+            "{}",
+            format!("Added {} to .externkit/.gitignore", added.join(", ")).green()
+        ),
+        Err(e) => println!(
+            "{}",
+            format!("Failed to update .externkit/.gitignore: {}", e).red()
+        ),
+    }
+}
+
+/// Appends the `GITIGNORE_RULES` that `.externkit/.gitignore` lacks, keeping
+/// the previous file in the backup rotation, and returns them. With
+/// `dry_run` only returns them.
+pub fn update_gitignore(
+    project_path: &std::path::Path,
+    dry_run: bool,
+) -> std::io::Result<Vec<&'static str>> {
+    let path = project_path.join(".gitignore");
+    let mut content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let missing: Vec<&str> = GITIGNORE_RULES
+        .into_iter()
+        .filter(|rule| !content.lines().any(|line| line.trim() == *rule))
+        .collect();
+    if missing.is_empty() || dry_run {
+        return Ok(missing);
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for rule in &missing {
+        content.push_str(rule);
+        content.push('\n');
+    }
+    externkit::env::storage::replace(project_path, &path, content.as_bytes())?;
+    Ok(missing)
+}
+
+/// Reads a passphrase without echoing it. Falls back to a plain line read
+/// when stdin is not a terminal so the passphrase can be piped in.
+pub fn prompt_passphrase(prompt: &str) -> std::io::Result<String> {
+    use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+    use std::io::{IsTerminal, Write};
+
+    // The prompt goes to stderr so `$(externkit env show KEY)` stays clean.
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+
+    if !std::io::stdin().is_terminal() {
+        let mut line = String::new();
+        std::io::stdin().read_line(&mut line)?;
+        eprintln!();
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    crossterm::terminal::enable_raw_mode()?;
+    let mut passphrase = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(KeyEvent {
+                code,
+                modifiers,
+                kind: KeyEventKind::Press,
+                ..
+            })) => match code {
+                KeyCode::Enter => break Ok(passphrase),
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(std::io::Error::new(
+                        std::io::ErrorKind::Interrupted,
+                        "Passphrase entry cancelled.",
+                    ))
+                }
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    crossterm::terminal::disable_raw_mode()?;
+    eprintln!();
+    result
+}
+
+pub fn prompt_new_passphrase() -> std::io::Result<String> {
+    let passphrase = prompt_passphrase("New passphrase: ")?;
+    if passphrase.is_empty() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Passphrase cannot be empty.",
+        ));
+    }
+    if prompt_passphrase("Confirm passphrase: ")? != passphrase {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Passphrases do not match.",
+        ));
     }
+    Ok(passphrase)
 }
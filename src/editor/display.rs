@@ -17,11 +17,7 @@ impl Display {
         execute!(stdout(), cursor::Hide)?;
         execute!(stdout(), cursor::MoveTo(0, 0))?;
 
-        let display_lines = if editor.terminal_height > 2 {
-            editor.terminal_height - 2
-        } else {
-            0
-        };
+        let display_lines = editor.terminal_height.saturating_sub(2);
 
         for i in 0..display_lines {
             let line_index = editor.offset_y + i;
@@ -58,7 +54,10 @@ impl Display {
         self.draw_help_bar(editor)?;
 
         let screen_y = editor.cursor_y - editor.offset_y;
-        let display_x = if editor.cursor_x > 0 && editor.terminal_width > 0 && editor.cursor_x >= editor.terminal_width {
+        let display_x = if editor.cursor_x > 0
+            && editor.terminal_width > 0
+            && editor.cursor_x >= editor.terminal_width
+        {
             editor.terminal_width.saturating_sub(1)
         } else {
             editor.cursor_x
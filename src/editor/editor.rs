@@ -67,10 +67,10 @@ impl Editor {
             display_result?;
 
             if let Event::Key(key_event) = event::read()? {
-                if key_event.kind == KeyEventKind::Press {
-                    if input_handler.process_key(self, key_event)? {
-                        break;
-                    }
+                if key_event.kind == KeyEventKind::Press
+                    && input_handler.process_key(self, key_event)?
+                {
+                    break;
                 }
             }
         }
@@ -143,7 +143,8 @@ impl Editor {
         if self.cursor_y < self.content.len().saturating_sub(1) {
             self.cursor_y += 1;
             // Prevent overflow by checking if terminal_height is large enough
-            if self.terminal_height > 2 && self.cursor_y >= self.offset_y + self.terminal_height - 2 {
+            if self.terminal_height > 2 && self.cursor_y >= self.offset_y + self.terminal_height - 2
+            {
                 self.offset_y = self.cursor_y.saturating_sub(self.terminal_height - 3);
             }
             self.clamp_cursor_x();
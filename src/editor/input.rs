@@ -23,10 +23,8 @@ impl InputHandler {
                 modifiers: KeyModifiers::CONTROL,
                 ..
             } => {
-                if editor.modified {
-                    if self.prompt_save(editor)? {
-                        self.save_file(editor)?;
-                    }
+                if editor.modified && self.prompt_save(editor)? {
+                    self.save_file(editor)?;
                 }
                 return Ok(true);
             }
@@ -73,10 +71,8 @@ impl InputHandler {
 
             KeyEvent {
                 code: KeyCode::End, ..
-            } => {
-                if editor.cursor_y < editor.content.len() {
-                    editor.cursor_x = editor.content[editor.cursor_y].len();
-                }
+            } if editor.cursor_y < editor.content.len() => {
+                editor.cursor_x = editor.content[editor.cursor_y].len();
             }
             KeyEvent {
                 code: KeyCode::Enter,
@@ -182,14 +178,12 @@ impl InputHandler {
                             return Ok(None);
                         }
                         KeyCode::Esc => return Ok(None),
-                        KeyCode::Backspace => {
-                            if !filename.is_empty() {
-                                filename.pop();
-                                execute!(stdout(), cursor::MoveLeft(1))?;
-                                execute!(stdout(), Print(" "))?;
-                                execute!(stdout(), cursor::MoveLeft(1))?;
-                                stdout().flush()?;
-                            }
+                        KeyCode::Backspace if !filename.is_empty() => {
+                            filename.pop();
+                            execute!(stdout(), cursor::MoveLeft(1))?;
+                            execute!(stdout(), Print(" "))?;
+                            execute!(stdout(), cursor::MoveLeft(1))?;
+                            stdout().flush()?;
                         }
                         KeyCode::Char(c) if !c.is_control() => {
                             filename.push(c);
@@ -1,4 +1,5 @@
 pub mod display;
+#[allow(clippy::module_inception)]
 pub mod editor;
 pub mod input;
 
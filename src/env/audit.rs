@@ -0,0 +1,188 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::path::Path;
+
+use super::value::Value;
+use super::{Error, Result};
+
+pub const AUDIT_FILE: &str = "audit.log";
+/// Random per-project key the value hashes are keyed with. Git-ignored like
+/// the log itself.
+pub const AUDIT_KEY_FILE: &str = "audit.key";
+
+/// One line of `.externkit/audit.log`. Values are only recorded as keyed
+/// hashes so the log never exposes them.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Entry {
+    pub id: u64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub user: String,
+    pub profile: String,
+    pub action: String,
+    pub key: String,
+    pub old_hash: Option<String>,
+    pub new_hash: Option<String>,
+}
+
+/// Reads the project's audit key, creating it on first use. Callers must
+/// hold the project's `StoreLock` so two processes cannot both create one.
+pub fn audit_key(project_dir: &Path) -> Result<Vec<u8>> {
+    let path = project_dir.join(AUDIT_KEY_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(content) => STANDARD.decode(content.trim()).map_err(|e| Error::Parse {
+            path,
+            line: 1,
+            column: 1,
+            message: e.to_string(),
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let mut key = vec![0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            super::storage::write_atomic(&path, STANDARD.encode(&key).as_bytes())?;
+            Ok(key)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// HMAC-SHA256 of a value under the project's audit key, hex encoded.
+/// Strings are hashed as they are and other values as compact JSON. The
+/// key keeps anyone holding only the log from confirming guessed values.
+pub fn hash_value(key: &[u8], value: &Value) -> String {
+    let text = match value {
+        Value::String(value) => value.clone(),
+        other => other.to_string(),
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(text.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Name of the OS user running the command: `$USER` or `$USERNAME`, else
+/// the account of the real user id.
+pub fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+        .or_else(account_name)
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(unix)]
+fn account_name() -> Option<String> {
+    // SAFETY: `getpwuid_r` only writes into `passwd` and `buffer`, whose
+    // sizes it is given, and `pw_name` points into `buffer` on success.
+    unsafe {
+        let uid = libc::getuid();
+        let mut passwd: libc::passwd = std::mem::zeroed();
+        let mut buffer = vec![0 as libc::c_char; 4096];
+        let mut result = std::ptr::null_mut();
+        let status = libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        );
+        if status != 0 || result.is_null() || passwd.pw_name.is_null() {
+            return Some(format!("uid:{}", uid));
+        }
+        Some(
+            std::ffi::CStr::from_ptr(passwd.pw_name)
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+#[cfg(not(unix))]
+fn account_name() -> Option<String> {
+    None
+}
+
+/// Reads every entry of the audit log, oldest first.
+pub fn read(project_dir: &Path) -> Result<Vec<Entry>> {
+    let path = project_dir.join(AUDIT_FILE);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(number, line)| {
+            serde_json::from_str(line).map_err(|e| match Error::parse(&path, &e) {
+                Error::Parse {
+                    path,
+                    column,
+                    message,
+                    ..
+                } => Error::Parse {
+                    path,
+                    line: number + 1,
+                    column,
+                    message,
+                },
+                other => other,
+            })
+        })
+        .collect()
+}
+
+/// Appends one entry per key that differs between `before` and `after`.
+/// Callers must hold the project's `StoreLock` so entry ids stay unique.
+pub fn record(
+    project_dir: &Path,
+    profile: &str,
+    action: &str,
+    before: &HashMap<String, Value>,
+    after: &HashMap<String, Value>,
+) -> Result<()> {
+    let keys: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    let changed: Vec<&String> = keys
+        .into_iter()
+        .filter(|key| before.get(*key) != after.get(*key))
+        .collect();
+    if changed.is_empty() {
+        return Ok(());
+    }
+
+    let first_id = read(project_dir)?.last().map_or(1, |entry| entry.id + 1);
+    let audit_key = audit_key(project_dir)?;
+    let timestamp = chrono::Utc::now();
+    let user = current_user();
+    let mut lines = String::new();
+    for (id, key) in (first_id..).zip(changed) {
+        let entry = Entry {
+            id,
+            timestamp,
+            user: user.clone(),
+            profile: profile.to_string(),
+            action: action.to_string(),
+            key: key.clone(),
+            old_hash: before.get(key).map(|value| hash_value(&audit_key, value)),
+            new_hash: after.get(key).map(|value| hash_value(&audit_key, value)),
+        };
+        lines.push_str(&serde_json::to_string(&entry).map_err(std::io::Error::other)?);
+        lines.push('\n');
+    }
+
+    let mut file = std::fs::File::options()
+        .create(true)
+        .append(true)
+        .open(project_dir.join(AUDIT_FILE))?;
+    file.write_all(lines.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::crypto::KeySource;
+use super::profiles::{self, ProfileConfig};
+use super::{Result, Store, Value};
+
+/// Modification time and length of a file, or `None` when it is missing.
+type Stamp = Option<(SystemTime, u64)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Merged variables and the names among them from the shared layer.
+pub type Loaded = (Arc<HashMap<String, Value>>, Arc<HashSet<String>>);
+
+struct CachedProfile {
+    stamps: Vec<Stamp>,
+    env_vars: Arc<HashMap<String, Value>>,
+    shared_keys: Arc<HashSet<String>>,
+}
+
+/// In-memory cache of merged profiles, invalidated whenever one of the files
+/// a profile was read from changes its modification time or size, or when a
+/// different project is loaded.
+#[derive(Default)]
+pub struct Cache {
+    project_dir: Option<std::path::PathBuf>,
+    config: Option<(Stamp, ProfileConfig)>,
+    profiles: HashMap<String, CachedProfile>,
+}
+
+impl Cache {
+    pub fn new() -> Self {
+        Cache::default()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.project_dir = None;
+        self.config = None;
+        self.profiles.clear();
+    }
+
+    /// Returns the merged variables of `requested` (or the active profile),
+    /// reading them from disk only if they changed since the last call.
+    pub fn load(
+        &mut self,
+        project_dir: &Path,
+        requested: Option<&str>,
+        key_source: Option<&KeySource>,
+    ) -> Result<Arc<HashMap<String, Value>>> {
+        self.load_with_shared_keys(project_dir, requested, key_source)
+            .map(|(env_vars, _)| env_vars)
+    }
+
+    /// Like `load`, along with the names `Store::merged_with_shared_keys`
+    /// reports as coming from the shared layer.
+    pub fn load_with_shared_keys(
+        &mut self,
+        project_dir: &Path,
+        requested: Option<&str>,
+        key_source: Option<&KeySource>,
+    ) -> Result<Loaded> {
+        let config_stamp = stamp(&project_dir.join(profiles::PROFILES_FILE));
+        let same_project = self.project_dir.as_deref() == Some(project_dir);
+        if !same_project || !matches!(&self.config, Some((cached, _)) if *cached == config_stamp) {
+            self.invalidate();
+            self.config = Some((config_stamp, ProfileConfig::load(project_dir)?));
+            self.project_dir = Some(project_dir.to_path_buf());
+        }
+        let (_, config) = self.config.as_ref().expect("loaded above");
+
+        let profile = config.resolve_profile(requested);
+        let chain = config.chain(&profile)?;
+        let stamps: Vec<Stamp> = std::iter::once(stamp(&profiles::shared_path(project_dir)))
+            .chain(chain.iter().flat_map(|name| {
+                [
+                    stamp(&profiles::store_path(project_dir, name)),
+                    stamp(&profiles::encrypted_store_path(project_dir, name)),
+                ]
+            }))
+            .collect();
+
+        if let Some(cached) = self.profiles.get(&profile) {
+            if cached.stamps == stamps {
+                return Ok((cached.env_vars.clone(), cached.shared_keys.clone()));
+            }
+        }
+        let (env_vars, shared_keys) =
+            Store::load(project_dir, Some(&profile), key_source.cloned())?
+                .merged_with_shared_keys()?;
+        let (env_vars, shared_keys) = (Arc::new(env_vars), Arc::new(shared_keys));
+        self.profiles.insert(
+            profile,
+            CachedProfile {
+                stamps,
+                env_vars: env_vars.clone(),
+                shared_keys: shared_keys.clone(),
+            },
+        );
+        Ok((env_vars, shared_keys))
+    }
+}
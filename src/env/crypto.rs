@@ -0,0 +1,139 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::{Error, Result};
+
+pub const ENCRYPTED_FILE: &str = "environment_variables.enc";
+
+const ENVELOPE_VERSION: u32 = 1;
+
+/// Where the secret used to derive the store key comes from.
+#[derive(Clone, Debug)]
+pub enum KeySource {
+    Passphrase(String),
+    Keyfile(PathBuf),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Passphrase,
+    Keyfile,
+}
+
+/// On-disk format of an encrypted store.
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    pub version: u32,
+    pub source: SourceKind,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+impl KeySource {
+    /// Reads `EXTERNKIT_KEYFILE` or `EXTERNKIT_PASSPHRASE`, in that order.
+    pub fn from_env() -> Option<Self> {
+        if let Ok(path) = std::env::var("EXTERNKIT_KEYFILE") {
+            return Some(KeySource::Keyfile(PathBuf::from(path)));
+        }
+        std::env::var("EXTERNKIT_PASSPHRASE")
+            .ok()
+            .map(KeySource::Passphrase)
+    }
+
+    pub fn kind(&self) -> SourceKind {
+        match self {
+            KeySource::Passphrase(_) => SourceKind::Passphrase,
+            KeySource::Keyfile(_) => SourceKind::Keyfile,
+        }
+    }
+
+    fn secret(&self) -> Result<Vec<u8>> {
+        match self {
+            KeySource::Passphrase(passphrase) => Ok(passphrase.as_bytes().to_vec()),
+            KeySource::Keyfile(path) => std::fs::read(path).map_err(|e| {
+                Error::Crypto(format!(
+                    "Failed to read keyfile '{}': {}",
+                    path.display(),
+                    e
+                ))
+            }),
+        }
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> Result<Key> {
+        let secret = self.secret()?;
+        if secret.is_empty() {
+            return Err(Error::Crypto(
+                "Passphrase or keyfile cannot be empty.".to_string(),
+            ));
+        }
+        let mut key = Key::default();
+        argon2::Argon2::default()
+            .hash_password_into(&secret, salt, &mut key)
+            .map_err(|e| Error::Crypto(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+pub fn encrypt(plaintext: &[u8], key_source: &KeySource) -> Result<Envelope> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let cipher = ChaCha20Poly1305::new(&key_source.derive_key(&salt)?);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| Error::Crypto("Encryption failed.".to_string()))?;
+
+    Ok(Envelope {
+        version: ENVELOPE_VERSION,
+        source: key_source.kind(),
+        salt: STANDARD.encode(salt),
+        nonce: STANDARD.encode(nonce),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+pub fn decrypt(envelope: &Envelope, key_source: &KeySource) -> Result<Vec<u8>> {
+    if envelope.version != ENVELOPE_VERSION {
+        return Err(Error::Crypto(format!(
+            "Unsupported encrypted store version {}.",
+            envelope.version
+        )));
+    }
+    let salt = decode_field(&envelope.salt, "salt")?;
+    let nonce = decode_field(&envelope.nonce, "nonce")?;
+    let ciphertext = decode_field(&envelope.ciphertext, "ciphertext")?;
+    if nonce.len() != 12 {
+        return Err(Error::Crypto(
+            "Encrypted store has a malformed nonce.".to_string(),
+        ));
+    }
+
+    let cipher = ChaCha20Poly1305::new(&key_source.derive_key(&salt)?);
+    cipher
+        .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| Error::Decrypt(envelope.source))
+}
+
+pub fn encrypt_to_string(plaintext: &[u8], key_source: &KeySource) -> Result<String> {
+    let envelope = encrypt(plaintext, key_source)?;
+    Ok(serde_json::to_string_pretty(&envelope).map_err(std::io::Error::other)?)
+}
+
+pub fn decrypt_str(content: &str, key_source: &KeySource) -> Result<Vec<u8>> {
+    let envelope: Envelope = serde_json::from_str(content)
+        .map_err(|e| Error::Crypto(format!("Encrypted store is malformed: {}", e)))?;
+    decrypt(&envelope, key_source)
+}
+
+fn decode_field(value: &str, field: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|_| Error::Crypto(format!("Encrypted store has a malformed {}.", field)))
+}
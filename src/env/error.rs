@@ -0,0 +1,215 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use super::crypto::SourceKind;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Everything that can go wrong while reading or writing a project's
+/// environment variables.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// A store file or `profiles.json` is not valid JSON. `env doctor` can
+    /// usually repair it.
+    Corrupt {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Another project file, such as the schema or the audit log, is not
+    /// valid JSON.
+    Parse {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The profile is encrypted and no passphrase or keyfile was given.
+    KeyRequired,
+    /// The passphrase or keyfile does not decrypt the profile.
+    Decrypt(SourceKind),
+    /// The key could not be derived or the encrypted store is malformed.
+    Crypto(String),
+    ProfileNotFound(String),
+    InvalidProfileName(String),
+    /// Profiles extend each other in a loop, listed in order.
+    ProfileCycle(Vec<String>),
+    UndefinedReference {
+        key: String,
+        reference: String,
+    },
+    /// `${NAME}` references form a loop, listed in order.
+    CyclicReference(Vec<String>),
+    UnterminatedReference(String),
+    InvalidPattern {
+        key: String,
+        message: String,
+    },
+    /// A secret reference such as `secret+cmd:pass show db` could not be resolved.
+    Secret {
+        key: String,
+        reference: String,
+        message: String,
+    },
+    /// The store was written but the change could not be added to the audit
+    /// log.
+    Audit(Box<Error>),
+}
+
+/// Splits serde's message from the position it appends to it.
+fn position(e: &serde_json::Error) -> (usize, usize, String) {
+    let message = e.to_string();
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    let message = message
+        .strip_suffix(&suffix)
+        .unwrap_or(&message)
+        .to_string();
+    (e.line(), e.column(), message)
+}
+
+impl Error {
+    pub fn corrupt(path: impl Into<PathBuf>, e: &serde_json::Error) -> Self {
+        let (line, column, message) = position(e);
+        Error::Corrupt {
+            path: path.into(),
+            line,
+            column,
+            message,
+        }
+    }
+
+    pub fn parse(path: impl Into<PathBuf>, e: &serde_json::Error) -> Self {
+        let (line, column, message) = position(e);
+        Error::Parse {
+            path: path.into(),
+            line,
+            column,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Corrupt {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Failed to parse {}: {} at line {} column {}. Run `externkit env doctor` to repair it.",
+                path.display(),
+                message,
+                line,
+                column
+            ),
+            Error::Parse {
+                path,
+                line,
+                column,
+                message,
+            } => write!(
+                f,
+                "Failed to parse {}: {} at line {} column {}.",
+                path.display(),
+                message,
+                line,
+                column
+            ),
+            Error::KeyRequired => f.write_str(
+                "Environment variables are encrypted. Set EXTERNKIT_PASSPHRASE or EXTERNKIT_KEYFILE to read them.",
+            ),
+            Error::Decrypt(source) => {
+                let hint = match source {
+                    SourceKind::Passphrase => "wrong passphrase",
+                    SourceKind::Keyfile => "wrong keyfile",
+                };
+                write!(f, "Failed to decrypt environment variables ({}?).", hint)
+            }
+            Error::Crypto(message) => f.write_str(message),
+            Error::ProfileNotFound(profile) => write!(
+                f,
+                "Profile '{}' does not exist. Use `externkit env use {}` to create it.",
+                profile, profile
+            ),
+            Error::InvalidProfileName(profile) => write!(
+                f,
+                "Invalid profile name '{}'. Use letters, digits, '-' and '_' only.",
+                profile
+            ),
+            Error::ProfileCycle(chain) => {
+                write!(f, "Profile inheritance cycle: {}", chain.join(" -> "))
+            }
+            Error::UndefinedReference { key, reference } => write!(
+                f,
+                "Environment variable '{}' references undefined variable '{}'.",
+                key, reference
+            ),
+            Error::CyclicReference(chain) => write!(
+                f,
+                "Cyclic reference between environment variables: {}",
+                chain.join(" -> ")
+            ),
+            Error::UnterminatedReference(key) => write!(
+                f,
+                "Unterminated reference in environment variable '{}'.",
+                key
+            ),
+            Error::InvalidPattern { key, message } => write!(
+                f,
+                "Invalid pattern for '{}' in {}: {}",
+                key,
+                super::schema::SCHEMA_FILE,
+                message
+            ),
+            Error::Secret {
+                key,
+                reference,
+                message,
+            } => write!(
+                f,
+                "Failed to resolve environment variable '{}' from {}: {}",
+                key, reference, message
+            ),
+            Error::Audit(e) => write!(f, "Failed to update the audit log: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Audit(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(e) => e,
+            Error::KeyRequired | Error::Decrypt(_) => {
+                io::Error::new(io::ErrorKind::PermissionDenied, e.to_string())
+            }
+            Error::ProfileNotFound(_) => io::Error::new(io::ErrorKind::NotFound, e.to_string()),
+            Error::InvalidProfileName(_) => {
+                io::Error::new(io::ErrorKind::InvalidInput, e.to_string())
+            }
+            _ => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+        }
+    }
+}
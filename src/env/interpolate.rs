@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+
+use super::secrets::Resolvers;
+use super::value::{self, Value};
+use super::{Error, Result};
+
+/// Expands `${NAME}` and `${NAME:-default}` references in stored values.
+///
+/// References resolve against the other stored keys first and the OS
+/// environment second; `$$` produces a literal `$`. A reference to an
+/// undefined variable without a default is an error, as is a cycle. Only
+/// string values are expanded; numbers, booleans, arrays and objects are
+/// used as their JSON text.
+///
+/// With `secrets`, expanded values that are secret references such as
+/// `secret+cmd:pass show db` are replaced by the secret. Keys marked `untrusted`
+/// are never resolved that way, and neither is any value that expands one
+/// of them, so an untrusted value cannot smuggle a command into a trusted
+/// one.
+pub struct Interpolator<'a> {
+    vars: &'a HashMap<String, Value>,
+    secrets: Option<&'a Resolvers>,
+    untrusted: Option<&'a HashSet<String>>,
+    /// Resolved keys whose value is untrusted or depends on one that is.
+    tainted: HashSet<String>,
+    resolved: HashMap<String, String>,
+}
+
+impl<'a> Interpolator<'a> {
+    pub fn new(vars: &'a HashMap<String, Value>) -> Self {
+        Interpolator {
+            vars,
+            secrets: None,
+            untrusted: None,
+            tainted: HashSet::new(),
+            resolved: HashMap::new(),
+        }
+    }
+
+    pub fn secrets(mut self, secrets: &'a Resolvers) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Keys whose values must not be resolved as secret references, such as
+    /// those from the committed shared file.
+    pub fn untrusted(mut self, keys: &'a HashSet<String>) -> Self {
+        self.untrusted = Some(keys);
+        self
+    }
+
+    /// Resolves a single stored key. Returns `None` when it is not stored.
+    pub fn resolve(&mut self, key: &str) -> Result<Option<String>> {
+        if !self.is_set(key) {
+            return Ok(None);
+        }
+        self.resolve_key(key, &mut Vec::new()).map(Some)
+    }
+
+    pub fn resolve_all(mut self) -> Result<HashMap<String, String>> {
+        for key in self.vars.keys() {
+            if self.is_set(key) {
+                self.resolve_key(key, &mut Vec::new())?;
+            }
+        }
+        Ok(self.resolved)
+    }
+
+    /// `null` values count as unset.
+    fn is_set(&self, key: &str) -> bool {
+        self.vars.get(key).is_some_and(|value| !value.is_null())
+    }
+
+    fn resolve_key(&mut self, key: &str, stack: &mut Vec<String>) -> Result<String> {
+        if let Some(value) = self.resolved.get(key) {
+            return Ok(value.clone());
+        }
+        if stack.iter().any(|name| name == key) {
+            stack.push(key.to_string());
+            return Err(Error::CyclicReference(stack.clone()));
+        }
+
+        stack.push(key.to_string());
+        let mut tainted = self.untrusted.is_some_and(|keys| keys.contains(key));
+        let value = match &self.vars[key] {
+            Value::String(raw) => {
+                let expanded = self.expand(raw, key, stack, &mut tainted)?;
+                match self.secrets {
+                    Some(secrets) if !tainted => {
+                        secrets.resolve(key, &expanded)?.unwrap_or(expanded)
+                    }
+                    _ => expanded,
+                }
+            }
+            other => value::as_env_string(other).unwrap_or_default(),
+        };
+        stack.pop();
+        if tainted {
+            self.tainted.insert(key.to_string());
+        }
+        self.resolved.insert(key.to_string(), value.clone());
+        Ok(value)
+    }
+
+    /// Sets `tainted` when `raw` expands an untrusted value.
+    fn expand(
+        &mut self,
+        raw: &str,
+        key: &str,
+        stack: &mut Vec<String>,
+        tainted: &mut bool,
+    ) -> Result<String> {
+        let mut value = String::with_capacity(raw.len());
+        let mut rest = raw;
+        while let Some(position) = rest.find('$') {
+            value.push_str(&rest[..position]);
+            rest = &rest[position..];
+            if rest.starts_with("$$") {
+                value.push('$');
+                rest = &rest[2..];
+                continue;
+            }
+            if !rest.starts_with("${") {
+                value.push('$');
+                rest = &rest[1..];
+                continue;
+            }
+
+            let end =
+                closing_brace(rest).ok_or_else(|| Error::UnterminatedReference(key.to_string()))?;
+            let reference = &rest[2..end];
+            rest = &rest[end + 1..];
+
+            let (name, default) = match reference.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (reference, None),
+            };
+            let found = if self.is_set(name) {
+                let found = self.resolve_key(name, stack)?;
+                *tainted |= self.tainted.contains(name);
+                Some(found)
+            } else {
+                std::env::var(name).ok()
+            };
+            match (found, default) {
+                (Some(found), None) => value.push_str(&found),
+                (Some(found), Some(_)) if !found.is_empty() => value.push_str(&found),
+                (_, Some(default)) => value.push_str(&self.expand(default, key, stack, tainted)?),
+                (None, None) => {
+                    return Err(Error::UndefinedReference {
+                        key: key.to_string(),
+                        reference: name.to_string(),
+                    })
+                }
+            }
+        }
+        value.push_str(rest);
+        Ok(value)
+    }
+}
+
+/// Finds the `}` closing the `${` at the start of `reference`, allowing
+/// nested references inside defaults.
+fn closing_brace(reference: &str) -> Option<usize> {
+    let mut depth = 0;
+    let bytes = reference.as_bytes();
+    for (position, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'{' if position > 0 && bytes[position - 1] == b'$' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Resolves `vars` without flattening them: references may still name
+/// flattened `PARENT__CHILD` variables, and objects are kept whole as their
+/// JSON text.
+pub fn resolve_nested(vars: &HashMap<String, Value>) -> Result<HashMap<String, String>> {
+    let mut resolved = Interpolator::new(&value::flatten(vars)).resolve_all()?;
+    for (key, value) in vars {
+        if value.is_object() {
+            resolved.insert(key.clone(), value.to_string());
+        }
+    }
+    resolved.retain(|key, _| vars.contains_key(key));
+    Ok(resolved)
+}
+
+/// Resolves a single key the way `resolve_nested` does, replacing secret
+/// references when `secrets` is given, except in `untrusted` keys.
+/// Flattened `PARENT__CHILD` names resolve too.
+pub fn resolve_one(
+    vars: &HashMap<String, Value>,
+    key: &str,
+    secrets: Option<&Resolvers>,
+    untrusted: &HashSet<String>,
+) -> Result<Option<String>> {
+    if let Some(value) = vars.get(key).filter(|value| value.is_object()) {
+        return Ok(Some(value.to_string()));
+    }
+    let flat = value::flatten(vars);
+    let mut interpolator = Interpolator::new(&flat).untrusted(untrusted);
+    if let Some(secrets) = secrets {
+        interpolator = interpolator.secrets(secrets);
+    }
+    interpolator.resolve(key)
+}
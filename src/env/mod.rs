@@ -0,0 +1,67 @@
+pub mod audit;
+pub mod cache;
+pub mod crypto;
+mod error;
+pub mod interpolate;
+pub mod profiles;
+pub mod repair;
+pub mod schema;
+pub mod secrets;
+pub mod storage;
+mod store;
+pub mod trust;
+pub mod value;
+
+pub use error::{Error, Result};
+pub use store::{Layer, Store};
+pub use value::Value;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use crypto::KeySource;
+
+pub fn is_encrypted(project_dir: &Path, profile: &str) -> bool {
+    profiles::encrypted_store_path(project_dir, profile).exists()
+}
+
+/// Parses the JSON content of a store file. `path` is only used in the error.
+pub fn parse_store(path: &Path, content: &[u8]) -> Result<HashMap<String, Value>> {
+    if content.iter().all(u8::is_ascii_whitespace) {
+        return Ok(HashMap::new());
+    }
+    serde_json::from_slice(content).map_err(|e| Error::corrupt(path, &e))
+}
+
+/// Reads the committed shared variables. They are never encrypted.
+pub fn read_shared_vars(project_dir: &Path) -> Result<HashMap<String, Value>> {
+    let path = profiles::shared_path(project_dir);
+    match std::fs::read(&path) {
+        Ok(content) => parse_store(&path, &content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads the variables stored directly in `profile`, without inheritance.
+/// `key_source` is only consulted when the profile has been encrypted.
+pub fn read_profile_vars(
+    project_dir: &Path,
+    profile: &str,
+    key_source: Option<&KeySource>,
+) -> Result<HashMap<String, Value>> {
+    if is_encrypted(project_dir, profile) {
+        let key_source = key_source.ok_or(Error::KeyRequired)?;
+        let path = profiles::encrypted_store_path(project_dir, profile);
+        let content = std::fs::read_to_string(&path)?;
+        let plaintext = crypto::decrypt_str(&content, key_source)?;
+        return parse_store(&path, &plaintext);
+    }
+
+    let path = profiles::store_path(project_dir, profile);
+    match std::fs::read(&path) {
+        Ok(content) => parse_store(&path, &content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
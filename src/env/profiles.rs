@@ -0,0 +1,127 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::{crypto, Error, Result};
+
+pub const DEFAULT_PROFILE: &str = "default";
+pub const PROFILES_FILE: &str = "profiles.json";
+pub const PROFILES_DIR: &str = "profiles";
+/// Variables meant to be committed and shared by everyone working on the
+/// project. Every profile inherits them.
+pub const SHARED_FILE: &str = "environment.shared.json";
+
+/// Contents of `.externkit/profiles.json`.
+#[derive(Serialize, Deserialize, Default)]
+pub struct ProfileConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileEntry>,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ProfileEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extends: Option<String>,
+}
+
+impl ProfileConfig {
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let path = project_dir.join(PROFILES_FILE);
+        if !path.exists() {
+            return Ok(ProfileConfig::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content).map_err(|e| Error::corrupt(&path, &e))
+    }
+
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        super::storage::write_atomic(&project_dir.join(PROFILES_FILE), content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Picks the profile to use: an explicit request wins, then
+    /// `EXTERNKIT_PROFILE`, then the profile selected with `env use`.
+    pub fn resolve_profile(&self, requested: Option<&str>) -> String {
+        requested
+            .map(str::to_string)
+            .or_else(|| std::env::var("EXTERNKIT_PROFILE").ok())
+            .or_else(|| self.active.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+
+    pub fn exists(&self, profile: &str) -> bool {
+        profile == DEFAULT_PROFILE || self.profiles.contains_key(profile)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names = vec![DEFAULT_PROFILE.to_string()];
+        names.extend(
+            self.profiles
+                .keys()
+                .filter(|name| name.as_str() != DEFAULT_PROFILE)
+                .cloned(),
+        );
+        names
+    }
+
+    /// Returns the inheritance chain of `profile`, base profile first.
+    pub fn chain(&self, profile: &str) -> Result<Vec<String>> {
+        let mut chain = vec![profile.to_string()];
+        let mut current = profile;
+        while let Some(base) = self
+            .profiles
+            .get(current)
+            .and_then(|entry| entry.extends.as_deref())
+        {
+            if chain.iter().any(|name| name == base) {
+                chain.push(base.to_string());
+                return Err(Error::ProfileCycle(chain));
+            }
+            chain.push(base.to_string());
+            current = base;
+        }
+        chain.reverse();
+        Ok(chain)
+    }
+}
+
+pub fn validate_name(profile: &str) -> Result<()> {
+    let valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidProfileName(profile.to_string()))
+    }
+}
+
+/// Plaintext store file of a profile. The default profile keeps using the
+/// original `environment_variables.json`.
+pub fn store_path(project_dir: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        project_dir.join("environment_variables.json")
+    } else {
+        project_dir
+            .join(PROFILES_DIR)
+            .join(format!("{}.json", profile))
+    }
+}
+
+pub fn shared_path(project_dir: &Path) -> PathBuf {
+    project_dir.join(SHARED_FILE)
+}
+
+pub fn encrypted_store_path(project_dir: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        project_dir.join(crypto::ENCRYPTED_FILE)
+    } else {
+        project_dir
+            .join(PROFILES_DIR)
+            .join(format!("{}.enc", profile))
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::io;
+
+use super::value::Value;
+
+/// Result of repairing a damaged store file.
+pub struct Repair {
+    pub env_vars: HashMap<String, Value>,
+    /// Human readable description of every change that was needed.
+    pub fixes: Vec<String>,
+}
+
+/// Parses store content leniently, undoing the damage hand edits and
+/// interrupted writes usually cause: a byte order mark, `//` comments,
+/// trailing commas and a truncated end of file. Fails when the content is
+/// not recognisable as a store at all.
+pub fn repair_store(content: &str) -> io::Result<Repair> {
+    let (value, fixes) = repair_json(content)?;
+    if value.is_null() {
+        return Ok(Repair {
+            env_vars: HashMap::new(),
+            fixes,
+        });
+    }
+    let Value::Object(object) = value else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Could not repair the file: it does not contain a JSON object.",
+        ));
+    };
+
+    Ok(Repair {
+        env_vars: object.into_iter().collect(),
+        fixes,
+    })
+}
+
+/// Parses any JSON file of the project leniently, returning the value and the
+/// fixes that were needed. An empty file yields `null`.
+pub fn repair_json(content: &str) -> io::Result<(serde_json::Value, Vec<String>)> {
+    let mut fixes = Vec::new();
+    let mut content = content;
+    if let Some(stripped) = content.strip_prefix('\u{feff}') {
+        fixes.push("Removed a byte order mark".to_string());
+        content = stripped;
+    }
+    if content.trim().is_empty() {
+        fixes.push("Replaced an empty file with an empty object".to_string());
+        return Ok((serde_json::Value::Null, fixes));
+    }
+    match serde_json::from_str(content) {
+        Ok(value) => Ok((value, fixes)),
+        Err(original) => match repair_text(content, &mut fixes) {
+            Some(value) => Ok((value, fixes)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Could not repair the file: {}", original),
+            )),
+        },
+    }
+}
+
+/// Text-level repairs, tried on content that failed to parse.
+fn repair_text(content: &str, fixes: &mut Vec<String>) -> Option<serde_json::Value> {
+    let mut output = String::with_capacity(content.len());
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut comments = 0;
+    let mut trailing_commas = 0;
+    // End of the last complete top-level entry, for cutting off a truncated
+    // one.
+    let mut last_entry_end = None;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                comments += 1;
+                while chars.next_if(|next| *next != '\n').is_some() {}
+            }
+            '{' | '[' => {
+                closers.push(if c == '{' { '}' } else { ']' });
+                output.push(c);
+            }
+            '}' | ']' => {
+                let trimmed = output.trim_end().len();
+                if output[..trimmed].ends_with(',') {
+                    output.truncate(trimmed - 1);
+                    trailing_commas += 1;
+                }
+                closers.pop();
+                output.push(c);
+            }
+            ',' => {
+                if closers.len() == 1 {
+                    last_entry_end = Some(output.len());
+                }
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    if comments > 0 {
+        fixes.push(format!("Removed {} comment(s)", comments));
+    }
+    if trailing_commas > 0 {
+        fixes.push(format!("Removed {} trailing comma(s)", trailing_commas));
+    }
+    if let Ok(value) = serde_json::from_str(&output) {
+        return Some(value);
+    }
+
+    // The file ends early, most likely an interrupted write: close what was
+    // left open, or drop the incomplete last entry.
+    // A string cut short is never closed, as its value would be incomplete.
+    if !in_string {
+        let mut closed = output.clone();
+        closed.extend(closers.iter().rev());
+        if let Ok(value) = serde_json::from_str(&closed) {
+            fixes.push("Closed the truncated end of the file".to_string());
+            return Some(value);
+        }
+    }
+    let end = last_entry_end?;
+    let value = serde_json::from_str(&format!("{}}}", &output[..end])).ok()?;
+    fixes.push("Dropped an incomplete entry at the end of the file".to_string());
+    Some(value)
+}
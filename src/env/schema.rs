@@ -0,0 +1,147 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use super::{Error, Result};
+
+pub const SCHEMA_FILE: &str = "env.schema.json";
+
+/// Contents of `.externkit/env.schema.json`, a small subset of JSON Schema:
+///
+/// ```json
+/// {
+///   "required": ["DATABASE_URL", "PORT"],
+///   "properties": {
+///     "PORT": { "type": "integer" },
+///     "LOG_LEVEL": { "enum": ["debug", "info", "warning"] },
+///     "API_URL": { "type": "url", "pattern": "^https://" }
+///   }
+/// }
+/// ```
+#[derive(Deserialize, Default)]
+pub struct Schema {
+    #[serde(default)]
+    pub required: Vec<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, Property>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct Property {
+    #[serde(default, rename = "type")]
+    pub value_type: ValueType,
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default, rename = "enum")]
+    pub allowed: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Url,
+    Json,
+}
+
+pub struct Violation {
+    pub key: String,
+    pub message: String,
+}
+
+impl Schema {
+    /// Loads the schema, or returns `None` when the project has none.
+    pub fn load(project_dir: &Path) -> Result<Option<Self>> {
+        let path = project_dir.join(SCHEMA_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::parse(&path, &e))
+    }
+
+    pub fn validate(&self, env_vars: &HashMap<String, String>) -> Result<Vec<Violation>> {
+        let mut violations = Vec::new();
+        for key in &self.required {
+            if !env_vars.contains_key(key) {
+                violations.push(Violation {
+                    key: key.clone(),
+                    message: "required but not set".to_string(),
+                });
+            }
+        }
+
+        for (key, property) in &self.properties {
+            let Some(value) = env_vars.get(key) else {
+                continue;
+            };
+            let mut violation = |message: String| {
+                violations.push(Violation {
+                    key: key.clone(),
+                    message,
+                })
+            };
+
+            if !property.value_type.accepts(value) {
+                violation(format!("is not a valid {}", property.value_type));
+            }
+            if let Some(pattern) = &property.pattern {
+                let regex = regex::Regex::new(pattern).map_err(|e| Error::InvalidPattern {
+                    key: key.clone(),
+                    message: e.to_string(),
+                })?;
+                if !regex.is_match(value) {
+                    violation(format!("does not match pattern '{}'", pattern));
+                }
+            }
+            if let Some(allowed) = &property.allowed {
+                if !allowed.contains(value) {
+                    violation(format!("is not one of: {}", allowed.join(", ")));
+                }
+            }
+        }
+        Ok(violations)
+    }
+}
+
+impl ValueType {
+    pub fn accepts(self, value: &str) -> bool {
+        match self {
+            ValueType::String => true,
+            ValueType::Integer => value.trim().parse::<i64>().is_ok(),
+            ValueType::Number => value.trim().parse::<f64>().is_ok(),
+            ValueType::Boolean => parse_bool(value).is_some(),
+            ValueType::Url => url::Url::parse(value.trim()).is_ok(),
+            ValueType::Json => serde_json::from_str::<serde_json::Value>(value).is_ok(),
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ValueType::String => "string",
+            ValueType::Integer => "integer",
+            ValueType::Number => "number",
+            ValueType::Boolean => "boolean",
+            ValueType::Url => "URL",
+            ValueType::Json => "JSON value",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Accepts `true/false`, `yes/no`, `on/off` and `1/0`, ignoring case.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
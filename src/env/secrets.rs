@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use super::{Error, Result};
+
+/// What a stored value starts with to be a secret reference, ahead of the
+/// resolver's prefix. Values without it are plain text, even `cmd:ls`.
+pub const MARKER: &str = "secret+";
+
+/// Looks up secrets that stored values point to instead of containing them,
+/// such as `secret+file:///run/secrets/db_pass` or `secret+cmd:pass show db`.
+pub trait Resolver: Send + Sync {
+    /// What a stored value starts with after [`MARKER`] to be resolved by this
+    /// resolver.
+    fn prefix(&self) -> &str;
+
+    /// Returns the secret `reference`, the value without the prefix, points
+    /// to. The error message is shown along with the variable's name.
+    fn resolve(&self, reference: &str) -> std::result::Result<String, String>;
+}
+
+/// `secret+file://<path>`: the content of a file, without a final newline.
+pub struct FileResolver;
+
+impl Resolver for FileResolver {
+    fn prefix(&self) -> &str {
+        "file://"
+    }
+
+    fn resolve(&self, reference: &str) -> std::result::Result<String, String> {
+        let content = std::fs::read_to_string(reference).map_err(|e| e.to_string())?;
+        Ok(strip_newline(content))
+    }
+}
+
+/// `secret+cmd:<command>`: the output of a shell command, without a final newline.
+/// The command failing is an error.
+pub struct CommandResolver;
+
+impl Resolver for CommandResolver {
+    fn prefix(&self) -> &str {
+        "cmd:"
+    }
+
+    fn resolve(&self, reference: &str) -> std::result::Result<String, String> {
+        #[cfg(unix)]
+        let mut command = {
+            let mut command = std::process::Command::new("sh");
+            command.arg("-c").arg(reference);
+            command
+        };
+        #[cfg(not(unix))]
+        let mut command = {
+            let mut command = std::process::Command::new("cmd");
+            command.arg("/C").arg(reference);
+            command
+        };
+        let output = command
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(match stderr.trim() {
+                "" => format!("the command failed ({})", output.status),
+                stderr => format!("the command failed ({}): {}", output.status, stderr),
+            });
+        }
+        String::from_utf8(output.stdout)
+            .map(strip_newline)
+            .map_err(|_| "the command printed invalid UTF-8".to_string())
+    }
+}
+
+/// `secret+keyring:<service>/<user>`: a password from the operating system's
+/// credential store.
+pub struct KeyringResolver;
+
+impl Resolver for KeyringResolver {
+    fn prefix(&self) -> &str {
+        "keyring:"
+    }
+
+    fn resolve(&self, reference: &str) -> std::result::Result<String, String> {
+        let (service, user) = reference
+            .split_once('/')
+            .ok_or_else(|| "expected secret+keyring:<service>/<user>".to_string())?;
+        keyring::Entry::new(service, user)
+            .and_then(|entry| entry.get_password())
+            .map_err(|e| e.to_string())
+    }
+}
+
+fn strip_newline(mut value: String) -> String {
+    if value.ends_with('\n') {
+        value.pop();
+        if value.ends_with('\r') {
+            value.pop();
+        }
+    }
+    value
+}
+
+/// The registered resolvers and the secrets they returned.
+///
+/// Secrets are cached by reference for `ttl`, forever when it is `None`;
+/// a zero `ttl` disables the cache.
+pub struct Resolvers {
+    resolvers: RwLock<Vec<Arc<dyn Resolver>>>,
+    ttl: RwLock<Option<Duration>>,
+    cache: Mutex<HashMap<String, (Instant, String)>>,
+}
+
+impl Resolvers {
+    /// A registry with the file, command and keyring resolvers.
+    pub fn new() -> Self {
+        Resolvers {
+            resolvers: RwLock::new(vec![
+                Arc::new(FileResolver),
+                Arc::new(CommandResolver),
+                Arc::new(KeyringResolver),
+            ]),
+            ttl: RwLock::new(None),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Adds `resolver`, replacing one registered for the same prefix.
+    pub fn register(&self, resolver: Arc<dyn Resolver>) {
+        let mut resolvers = self.resolvers.write().unwrap();
+        resolvers.retain(|existing| existing.prefix() != resolver.prefix());
+        resolvers.push(resolver);
+    }
+
+    pub fn set_ttl(&self, ttl: Option<Duration>) {
+        *self.ttl.write().unwrap() = ttl;
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+
+    /// Whether `value` starts with [`MARKER`] and the prefix of a registered
+    /// resolver.
+    pub fn is_reference(&self, value: &str) -> bool {
+        self.find(value).is_some()
+    }
+
+    /// The resolver `value` refers to and the reference it is given.
+    fn find<'v>(&self, value: &'v str) -> Option<(Arc<dyn Resolver>, &'v str)> {
+        let value = value.strip_prefix(MARKER)?;
+        self.resolvers.read().unwrap().iter().find_map(|resolver| {
+            let reference = value.strip_prefix(resolver.prefix())?;
+            Some((resolver.clone(), reference))
+        })
+    }
+
+    /// Returns the secret `value` points to, or `None` when it is not a
+    /// reference. `key` is only used in the error.
+    pub fn resolve(&self, key: &str, value: &str) -> Result<Option<String>> {
+        let Some((resolver, reference)) = self.find(value) else {
+            return Ok(None);
+        };
+
+        let ttl = *self.ttl.read().unwrap();
+        if let Some((resolved_at, secret)) = self.cache.lock().unwrap().get(value) {
+            if ttl.is_none_or(|ttl| resolved_at.elapsed() < ttl) {
+                return Ok(Some(secret.clone()));
+            }
+        }
+        let secret = resolver
+            .resolve(reference)
+            .map_err(|message| Error::Secret {
+                key: key.to_string(),
+                reference: value.to_string(),
+                message,
+            })?;
+        if ttl != Some(Duration::ZERO) {
+            self.cache
+                .lock()
+                .unwrap()
+                .insert(value.to_string(), (Instant::now(), secret.clone()));
+        }
+        Ok(Some(secret))
+    }
+}
+
+impl Default for Resolvers {
+    fn default() -> Self {
+        Resolvers::new()
+    }
+}
+
+static REGISTRY: LazyLock<Resolvers> = LazyLock::new(|| {
+    let resolvers = Resolvers::new();
+    if let Some(seconds) = std::env::var("EXTERNKIT_SECRET_CACHE_TTL")
+        .ok()
+        .and_then(|ttl| ttl.trim().parse::<f64>().ok())
+    {
+        resolvers.set_ttl(Duration::try_from_secs_f64(seconds).ok());
+    }
+    resolvers
+});
+
+/// The resolvers used by `externkit run`, the shell hook and the Python
+/// module. `EXTERNKIT_SECRET_CACHE_TTL` sets its cache lifetime in seconds.
+pub fn registry() -> &'static Resolvers {
+    &REGISTRY
+}
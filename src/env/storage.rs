@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const LOCK_FILE: &str = ".lock";
+pub const BACKUP_DIR: &str = "backups";
+pub const MAX_BACKUPS: usize = 5;
+
+/// Exclusive advisory lock on a project, held for a whole read-modify-write
+/// cycle. Released when dropped.
+///
+/// Every change externkit makes happens under this lock, so a project that
+/// was trusted when the lock was taken is trusted again with the new files
+/// before it is released. Changes made any other way withdraw the trust.
+pub struct StoreLock {
+    _file: File,
+    project_dir: PathBuf,
+    trusted: bool,
+}
+
+impl StoreLock {
+    pub fn acquire(project_dir: &Path) -> io::Result<Self> {
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(project_dir.join(LOCK_FILE))?;
+        file.lock()?;
+        Ok(StoreLock {
+            _file: file,
+            project_dir: project_dir.to_path_buf(),
+            trusted: super::trust::is_trusted(project_dir),
+        })
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        // Failing leaves the project untrusted, which only means asking
+        // for `externkit env allow` again.
+        if self.trusted && !super::trust::is_trusted(&self.project_dir) {
+            let _ = super::trust::allow(&self.project_dir);
+        }
+    }
+}
+
+/// Replaces `path` with `content` by writing a temporary file next to it and
+/// renaming it into place, so readers never observe a partial write.
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent)?;
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid store path."))?;
+    let temp_path = parent.join(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = (|| {
+        let mut file = File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Path of backup `index` (1 is the newest) of a file inside `project_dir`.
+pub fn backup_path(project_dir: &Path, path: &Path, index: usize) -> PathBuf {
+    let relative = path.strip_prefix(project_dir).unwrap_or(path);
+    let mut backup = project_dir.join(BACKUP_DIR).join(relative).into_os_string();
+    backup.push(format!(".{}", index));
+    PathBuf::from(backup)
+}
+
+/// Copies `path` into the backup rotation before it is overwritten, keeping
+/// the newest `MAX_BACKUPS` copies.
+pub fn backup(project_dir: &Path, path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    for index in (1..MAX_BACKUPS).rev() {
+        let from = backup_path(project_dir, path, index);
+        if from.exists() {
+            std::fs::rename(&from, backup_path(project_dir, path, index + 1))?;
+        }
+    }
+    let newest = backup_path(project_dir, path, 1);
+    if let Some(parent) = newest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::copy(path, newest)?;
+    Ok(())
+}
+
+/// Backs up and then atomically replaces a store file.
+pub fn replace(project_dir: &Path, path: &Path, content: &[u8]) -> io::Result<()> {
+    backup(project_dir, path)?;
+    write_atomic(path, content)
+}
+
+/// Removes a store file after copying it into the backup rotation.
+pub fn remove(project_dir: &Path, path: &Path) -> io::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    backup(project_dir, path)?;
+    std::fs::remove_file(path)
+}
+
+/// Deletes `path` and every copy of it in the backup rotation. Used for
+/// plaintext stores once they are encrypted, so no copy of the secrets is
+/// left in cleartext.
+pub fn purge(project_dir: &Path, path: &Path) -> io::Result<()> {
+    for (_, backup) in list_backups(project_dir, path) {
+        std::fs::remove_file(backup)?;
+    }
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Lists the existing backups of `path`, newest first.
+pub fn list_backups(project_dir: &Path, path: &Path) -> Vec<(usize, PathBuf)> {
+    (1..=MAX_BACKUPS)
+        .map(|index| (index, backup_path(project_dir, path, index)))
+        .filter(|(_, backup)| backup.exists())
+        .collect()
+}
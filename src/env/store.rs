@@ -0,0 +1,316 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::crypto::{self, KeySource};
+use super::profiles::{self, ProfileConfig};
+use super::secrets::Resolvers;
+use super::storage::{self, StoreLock};
+use super::value::{self, Value};
+use super::{audit, interpolate, Error, Result};
+
+/// Where a merged variable comes from. Later layers override earlier ones:
+/// the committed shared file first, then each profile of the inheritance
+/// chain, base profile first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    Shared,
+    Profile(String),
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Shared => f.write_str("shared"),
+            Layer::Profile(name) => f.write_str(name),
+        }
+    }
+}
+
+/// The variables of one profile of a project. Values are JSON values, so
+/// besides strings they can be numbers, booleans, arrays or objects.
+///
+/// `get`, `set` and `remove` work on the profile's own variables in memory;
+/// `save` writes them back, encrypted again if the profile is, and records
+/// the changes in the audit log. `merged` and `resolved` add the shared
+/// variables and the profiles it inherits from and expand `${NAME}`
+/// references into plain strings.
+///
+/// Use `load_locked` for read-modify-write cycles: the project lock is then
+/// held until the store is dropped, so concurrent writers cannot lose each
+/// other's updates.
+pub struct Store {
+    project_dir: PathBuf,
+    config: ProfileConfig,
+    profile: String,
+    encrypted: bool,
+    key_source: Option<KeySource>,
+    vars: HashMap<String, Value>,
+    saved: HashMap<String, Value>,
+    _lock: Option<StoreLock>,
+}
+
+impl Store {
+    /// Loads `profile`, or the active profile when `None`. `key_source` is
+    /// needed when the profile or one it inherits from is encrypted.
+    pub fn load(
+        project_dir: &Path,
+        profile: Option<&str>,
+        key_source: Option<KeySource>,
+    ) -> Result<Self> {
+        Store::open(project_dir, profile, key_source, None)
+    }
+
+    /// Like `load`, holding the project lock until the store is dropped.
+    pub fn load_locked(
+        project_dir: &Path,
+        profile: Option<&str>,
+        key_source: Option<KeySource>,
+    ) -> Result<Self> {
+        let lock = StoreLock::acquire(project_dir)?;
+        Store::open(project_dir, profile, key_source, Some(lock))
+    }
+
+    fn open(
+        project_dir: &Path,
+        profile: Option<&str>,
+        key_source: Option<KeySource>,
+        lock: Option<StoreLock>,
+    ) -> Result<Self> {
+        let config = ProfileConfig::load(project_dir)?;
+        let profile = config.resolve_profile(profile);
+        if !config.exists(&profile) {
+            return Err(Error::ProfileNotFound(profile));
+        }
+        let vars = super::read_profile_vars(project_dir, &profile, key_source.as_ref())?;
+        Ok(Store {
+            project_dir: project_dir.to_path_buf(),
+            encrypted: super::is_encrypted(project_dir, &profile),
+            config,
+            profile,
+            key_source,
+            saved: vars.clone(),
+            vars,
+            _lock: lock,
+        })
+    }
+
+    pub fn project_dir(&self) -> &Path {
+        &self.project_dir
+    }
+
+    pub fn profile(&self) -> &str {
+        &self.profile
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+
+    pub fn key_source(&self) -> Option<&KeySource> {
+        self.key_source.as_ref()
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.vars.get(key)
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.vars.contains_key(key)
+    }
+
+    /// Sets `key`, returning its previous value.
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<Value>) -> Option<Value> {
+        self.vars.insert(key.into(), value.into())
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        self.vars.remove(key)
+    }
+
+    pub fn clear(&mut self) {
+        self.vars.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.vars.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.vars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.is_empty()
+    }
+
+    /// The profile's own variables, without inheritance.
+    pub fn vars(&self) -> &HashMap<String, Value> {
+        &self.vars
+    }
+
+    /// Every layer's own variables, lowest precedence first.
+    pub fn layers(&self) -> Result<Vec<(Layer, HashMap<String, Value>)>> {
+        let mut layers = vec![(Layer::Shared, super::read_shared_vars(&self.project_dir)?)];
+        for name in self.config.chain(&self.profile)? {
+            let vars = if name == self.profile {
+                self.vars.clone()
+            } else {
+                super::read_profile_vars(&self.project_dir, &name, self.key_source.as_ref())?
+            };
+            layers.push((Layer::Profile(name), vars));
+        }
+        Ok(layers)
+    }
+
+    /// The profile's variables on top of the profiles it inherits from and
+    /// the shared variables.
+    pub fn merged(&self) -> Result<HashMap<String, Value>> {
+        let mut merged = HashMap::new();
+        for (_, vars) in self.layers()? {
+            merged.extend(vars);
+        }
+        Ok(merged)
+    }
+
+    /// `merged` along with the flattened names whose value comes from the
+    /// shared layer. Those are never resolved as secret references: the
+    /// shared file is committed, so anyone with push access could otherwise
+    /// make everyone's `externkit run` execute a `secret+cmd:` reference.
+    pub fn merged_with_shared_keys(&self) -> Result<(HashMap<String, Value>, HashSet<String>)> {
+        let mut merged = HashMap::new();
+        let mut shared = HashSet::new();
+        for (layer, vars) in self.layers()? {
+            for (key, value) in vars {
+                if layer == Layer::Shared {
+                    shared.insert(key.clone());
+                } else {
+                    shared.remove(&key);
+                }
+                merged.insert(key, value);
+            }
+        }
+        let shared_vars: HashMap<String, Value> = merged
+            .iter()
+            .filter(|(key, _)| shared.contains(*key))
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let shared_keys = value::flatten(&shared_vars).into_keys().collect();
+        Ok((merged, shared_keys))
+    }
+
+    /// The layer each variable of `merged` comes from.
+    pub fn origins(&self) -> Result<HashMap<String, Layer>> {
+        let mut origins = HashMap::new();
+        for (layer, vars) in self.layers()? {
+            for key in vars.into_keys() {
+                origins.insert(key, layer.clone());
+            }
+        }
+        Ok(origins)
+    }
+
+    /// `merged` as strings, with every `${NAME}` reference expanded. Objects
+    /// are kept whole as their JSON text.
+    pub fn resolved(&self) -> Result<HashMap<String, String>> {
+        interpolate::resolve_nested(&self.merged()?)
+    }
+
+    /// Like `resolved`, with nested objects flattened to `PARENT__CHILD`
+    /// names. This is what processes and dotenv files get.
+    pub fn resolved_flat(&self) -> Result<HashMap<String, String>> {
+        interpolate::Interpolator::new(&value::flatten(&self.merged()?)).resolve_all()
+    }
+
+    /// Checks that the `${NAME}` references in `keys` resolve against the
+    /// merged variables, so a value that cannot be expanded is refused
+    /// before it is saved rather than breaking every later read of the
+    /// profile. Secret references are not resolved.
+    pub fn check_references<'k>(&self, keys: impl IntoIterator<Item = &'k str>) -> Result<()> {
+        let merged = self.merged()?;
+        let flat = value::flatten(&merged);
+        let mut interpolator = interpolate::Interpolator::new(&flat);
+        for key in keys {
+            if !merged.get(key).is_some_and(Value::is_object) {
+                interpolator.resolve(key)?;
+                continue;
+            }
+            let prefix = format!("{}{}", key, value::NESTED_SEPARATOR);
+            for name in flat.keys().filter(|name| name.starts_with(&prefix)) {
+                interpolator.resolve(name)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `resolved_flat`, with secret references replaced by the secrets
+    /// they point to, except those from the shared layer. This is what
+    /// `externkit run` passes on.
+    pub fn resolved_secrets(&self, secrets: &Resolvers) -> Result<HashMap<String, String>> {
+        let (merged, shared_keys) = self.merged_with_shared_keys()?;
+        interpolate::Interpolator::new(&value::flatten(&merged))
+            .secrets(secrets)
+            .untrusted(&shared_keys)
+            .resolve_all()
+    }
+
+    /// Writes the variables back, keeping the previous file in the backup
+    /// rotation, and records what changed since the last save under
+    /// `action`. A failure to update the audit log is returned as
+    /// `Error::Audit` after the variables have been written.
+    pub fn save(&mut self, action: &str) -> Result<()> {
+        self.write(self.encrypted)?;
+        let before = std::mem::replace(&mut self.saved, self.vars.clone());
+        audit::record(
+            &self.project_dir,
+            &self.profile,
+            action,
+            &before,
+            &self.vars,
+        )
+        .map_err(|e| Error::Audit(Box::new(e)))
+    }
+
+    /// Re-writes the profile encrypted with `key_source` and deletes the
+    /// plaintext file along with its backups.
+    pub fn encrypt(&mut self, key_source: KeySource) -> Result<()> {
+        self.key_source = Some(key_source);
+        self.write(true)?;
+        self.encrypted = true;
+        storage::purge(
+            &self.project_dir,
+            &profiles::store_path(&self.project_dir, &self.profile),
+        )?;
+        Ok(())
+    }
+
+    /// Re-writes the profile as plaintext and removes the encrypted file.
+    pub fn decrypt(&mut self) -> Result<()> {
+        self.write(false)?;
+        self.encrypted = false;
+        storage::remove(
+            &self.project_dir,
+            &profiles::encrypted_store_path(&self.project_dir, &self.profile),
+        )?;
+        Ok(())
+    }
+
+    fn write(&self, encrypted: bool) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.vars).map_err(std::io::Error::other)?;
+        let (path, content) = if encrypted {
+            let key_source = self.key_source.as_ref().ok_or(Error::KeyRequired)?;
+            (
+                profiles::encrypted_store_path(&self.project_dir, &self.profile),
+                crypto::encrypt_to_string(content.as_bytes(), key_source)?,
+            )
+        } else {
+            (
+                profiles::store_path(&self.project_dir, &self.profile),
+                content,
+            )
+        };
+        storage::replace(&self.project_dir, &path, content.as_bytes())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use super::profiles::{self, ProfileConfig};
+use super::{Error, Result};
+
+/// Projects the user has allowed with `externkit env allow`, by directory,
+/// each with a fingerprint of the files the shell hook reads as they were
+/// when allowed. Kept outside of any project so a cloned repository cannot
+/// trust itself.
+fn trust_file() -> Option<PathBuf> {
+    #[cfg(windows)]
+    let config_dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(windows))]
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+    config_dir.map(|dir| dir.join("externkit").join("trusted.json"))
+}
+
+fn read_trusted(path: &Path) -> Result<BTreeMap<String, String>> {
+    match std::fs::read(path) {
+        Ok(content) => serde_json::from_slice(&content).map_err(|e| Error::parse(path, &e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn project_key(project_dir: &Path) -> String {
+    project_dir
+        .canonicalize()
+        .unwrap_or_else(|_| project_dir.to_path_buf())
+        .display()
+        .to_string()
+}
+
+/// The files the shell hook reads: the shared file, the profile
+/// configuration and the store of every profile, plaintext or encrypted.
+/// Only the shared file is committed, but a repository can ship the others
+/// too.
+fn hook_files(project_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![
+        profiles::shared_path(project_dir),
+        project_dir.join(profiles::PROFILES_FILE),
+    ];
+    for name in ProfileConfig::load(project_dir)?.names() {
+        files.push(profiles::store_path(project_dir, &name));
+        files.push(profiles::encrypted_store_path(project_dir, &name));
+    }
+    Ok(files)
+}
+
+/// SHA-256 over the names and contents of `hook_files`, so changing any of
+/// them, for example by pulling new commits, withdraws the trust until the
+/// project is allowed again.
+fn fingerprint(project_dir: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for path in hook_files(project_dir)? {
+        let relative = path.strip_prefix(project_dir).unwrap_or(&path);
+        hasher.update(relative.to_string_lossy().as_bytes());
+        match std::fs::read(&path) {
+            Ok(content) => {
+                hasher.update([1]);
+                hasher.update((content.len() as u64).to_le_bytes());
+                hasher.update(&content);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => hasher.update([0]),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Whether the shell hook may load the project's shared variables and
+/// resolve its secret references.
+pub fn is_trusted(project_dir: &Path) -> bool {
+    let Some(path) = trust_file() else {
+        return false;
+    };
+    let Ok(trusted) = read_trusted(&path) else {
+        return false;
+    };
+    trusted
+        .get(&project_key(project_dir))
+        .is_some_and(|allowed| fingerprint(project_dir).is_ok_and(|current| *allowed == current))
+}
+
+/// Trusts the project with its files as they are now.
+pub fn allow(project_dir: &Path) -> Result<()> {
+    let path = trust_file().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "No configuration directory to record trusted projects in.",
+        )
+    })?;
+    let mut trusted = read_trusted(&path)?;
+    trusted.insert(project_key(project_dir), fingerprint(project_dir)?);
+    write_trusted(&path, &trusted)
+}
+
+/// Withdraws the trust. Returns `false` when the project was not trusted.
+pub fn deny(project_dir: &Path) -> Result<bool> {
+    let Some(path) = trust_file() else {
+        return Ok(false);
+    };
+    let mut trusted = read_trusted(&path)?;
+    if trusted.remove(&project_key(project_dir)).is_none() {
+        return Ok(false);
+    }
+    write_trusted(&path, &trusted)?;
+    Ok(true)
+}
+
+fn write_trusted(path: &Path, trusted: &BTreeMap<String, String>) -> Result<()> {
+    let content = serde_json::to_string_pretty(trusted).map_err(std::io::Error::other)?;
+    super::storage::write_atomic(path, content.as_bytes())?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+
+pub use serde_json::Value;
+
+/// Joins the keys of nested objects when they are flattened into variable
+/// names: `{"DB": {"HOST": ..}}` becomes `DB__HOST`.
+pub const NESTED_SEPARATOR: &str = "__";
+
+/// How a stored value appears in a process environment: strings as they
+/// are, anything else as compact JSON. `null` means the variable is unset.
+pub fn as_env_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Replaces every object value with one variable per leaf, named by joining
+/// the keys with `NESTED_SEPARATOR`. Arrays stay whole. A variable stored
+/// under a flattened name directly wins over the nested one.
+pub fn flatten(vars: &HashMap<String, Value>) -> HashMap<String, Value> {
+    let mut flat = HashMap::new();
+    let mut nested = Vec::new();
+    for (key, value) in vars {
+        match value {
+            Value::Object(_) => flatten_into(key, value, &mut nested),
+            other => {
+                flat.insert(key.clone(), other.clone());
+            }
+        }
+    }
+    for (key, value) in nested {
+        flat.entry(key).or_insert(value);
+    }
+    flat
+}
+
+fn flatten_into(prefix: &str, value: &Value, flat: &mut Vec<(String, Value)>) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object {
+                flatten_into(
+                    &format!("{}{}{}", prefix, NESTED_SEPARATOR, key),
+                    value,
+                    flat,
+                );
+            }
+        }
+        other => flat.push((prefix.to_string(), other.clone())),
+    }
+}
@@ -1,23 +1,668 @@
 use pyo3::prelude::*;
 
+pub mod env;
+pub mod project;
 mod sqlite3;
 
-fn load_env_vars() -> std::collections::HashMap<String, String> {
-    let content =
-        std::fs::read_to_string(".externkit/environment_variables.json").unwrap_or_default();
-    if content.is_empty() {
-        std::collections::HashMap::new()
-    } else {
-        serde_json::from_str(&content).unwrap_or_default()
+static ENV_CACHE: std::sync::LazyLock<std::sync::Mutex<env::cache::Cache>> =
+    std::sync::LazyLock::new(|| std::sync::Mutex::new(env::cache::Cache::new()));
+
+static WATCHER: std::sync::Mutex<Option<Vec<PyObject>>> = std::sync::Mutex::new(None);
+
+/// Set at interpreter exit to end the watcher thread, and notified so it
+/// does not finish sleeping first.
+static WATCHER_STOP: (std::sync::Mutex<bool>, std::sync::Condvar) =
+    (std::sync::Mutex::new(false), std::sync::Condvar::new());
+
+const WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Broken references are a problem with the values themselves, anything else
+/// means the store could not be read.
+fn to_py_err(e: env::Error) -> PyErr {
+    match e {
+        env::Error::UndefinedReference { .. }
+        | env::Error::CyclicReference(_)
+        | env::Error::UnterminatedReference(_) => {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
+        }
+        _ => PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()),
+    }
+}
+
+fn load_env_vars(
+    profile: Option<&str>,
+) -> PyResult<std::sync::Arc<std::collections::HashMap<String, env::Value>>> {
+    load_env_vars_with_shared_keys(profile).map(|(env_vars, _)| env_vars)
+}
+
+/// Like `load_env_vars`, along with the names from the shared layer, whose
+/// secret references are never resolved.
+fn load_env_vars_with_shared_keys(profile: Option<&str>) -> PyResult<env::cache::Loaded> {
+    let Some(project_dir) = project::locate(None) else {
+        return Ok(Default::default());
+    };
+    let key_source = env::crypto::KeySource::from_env();
+    ENV_CACHE
+        .lock()
+        .unwrap()
+        .load_with_shared_keys(&project_dir, profile, key_source.as_ref())
+        .map_err(to_py_err)
+}
+
+fn lookup(name: &str, profile: Option<&str>) -> PyResult<Option<String>> {
+    match std::env::var(name) {
+        Ok(val) => Ok(Some(val)),
+        Err(_) => {
+            let (env_vars, shared_keys) = load_env_vars_with_shared_keys(profile)?;
+            // Also finds `PARENT__CHILD` names, as `externkit run` exports
+            // nested objects.
+            env::interpolate::resolve_one(
+                &env_vars,
+                name,
+                Some(env::secrets::registry()),
+                &shared_keys,
+            )
+            .map_err(to_py_err)
+        }
+    }
+}
+
+fn to_py(py: Python<'_>, value: &env::Value) -> PyResult<PyObject> {
+    use pyo3::IntoPyObjectExt;
+
+    match value {
+        env::Value::Null => Ok(py.None()),
+        env::Value::Bool(value) => value.into_py_any(py),
+        env::Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(value), _) => value.into_py_any(py),
+            (None, Some(value)) => value.into_py_any(py),
+            (None, None) => number.as_f64().unwrap_or(f64::NAN).into_py_any(py),
+        },
+        env::Value::String(value) => value.into_py_any(py),
+        env::Value::Array(items) => items
+            .iter()
+            .map(|item| to_py(py, item))
+            .collect::<PyResult<Vec<_>>>()?
+            .into_py_any(py),
+        env::Value::Object(object) => {
+            let dict = pyo3::types::PyDict::new(py);
+            for (key, value) in object {
+                dict.set_item(key, to_py(py, value)?)?;
+            }
+            dict.into_py_any(py)
+        }
+    }
+}
+
+fn invalid_value(name: &str, expected: &str) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+        "Environment variable '{}' is not a valid {}.",
+        name, expected
+    ))
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, profile=None))]
+fn get(name: String, profile: Option<String>) -> PyResult<Option<String>> {
+    lookup(&name, profile.as_deref())
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, default=None, profile=None))]
+fn get_int(name: String, default: Option<i64>, profile: Option<String>) -> PyResult<Option<i64>> {
+    match lookup(&name, profile.as_deref())? {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid_value(&name, "integer")),
+        None => Ok(default),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, default=None, profile=None))]
+fn get_float(name: String, default: Option<f64>, profile: Option<String>) -> PyResult<Option<f64>> {
+    match lookup(&name, profile.as_deref())? {
+        Some(value) => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| invalid_value(&name, "float")),
+        None => Ok(default),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, default=None, profile=None))]
+fn get_bool(
+    name: String,
+    default: Option<bool>,
+    profile: Option<String>,
+) -> PyResult<Option<bool>> {
+    match lookup(&name, profile.as_deref())? {
+        Some(value) => env::schema::parse_bool(&value)
+            .map(Some)
+            .ok_or_else(|| invalid_value(&name, "boolean")),
+        None => Ok(default),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, sep=",", default=None, profile=None))]
+fn get_list(
+    name: String,
+    sep: &str,
+    default: Option<Vec<String>>,
+    profile: Option<String>,
+) -> PyResult<Option<Vec<String>>> {
+    if sep.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Separator cannot be empty.",
+        ));
+    }
+    match lookup(&name, profile.as_deref())? {
+        Some(value) if value.trim().is_empty() => Ok(Some(Vec::new())),
+        Some(value) => Ok(Some(
+            value
+                .split(sep)
+                .map(|item| item.trim().to_string())
+                .collect(),
+        )),
+        None => Ok(default),
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, default=None, profile=None))]
+fn get_json(
+    py: Python<'_>,
+    name: String,
+    default: Option<PyObject>,
+    profile: Option<String>,
+) -> PyResult<PyObject> {
+    match lookup(&name, profile.as_deref())? {
+        Some(value) => Ok(py.import("json")?.call_method1("loads", (value,))?.unbind()),
+        None => Ok(default.unwrap_or_else(|| py.None())),
+    }
+}
+
+/// Returns the stored value with its JSON type. A value set in the OS
+/// environment still wins; it is parsed as JSON when the stored value is not
+/// a string, so types survive `externkit run`.
+#[pyfunction]
+#[pyo3(signature = (name, default=None, profile=None))]
+fn get_typed(
+    py: Python<'_>,
+    name: String,
+    default: Option<PyObject>,
+    profile: Option<String>,
+) -> PyResult<PyObject> {
+    let (env_vars, shared_keys) = load_env_vars_with_shared_keys(profile.as_deref())?;
+    let stored = env_vars.get(&name).filter(|value| !value.is_null());
+    let value = match (std::env::var(&name).ok(), stored) {
+        (Some(os_value), Some(stored)) if !stored.is_string() => {
+            serde_json::from_str(&os_value).unwrap_or(env::Value::String(os_value))
+        }
+        (Some(os_value), _) => env::Value::String(os_value),
+        (None, Some(env::Value::String(_))) => env::interpolate::resolve_one(
+            &env_vars,
+            &name,
+            Some(env::secrets::registry()),
+            &shared_keys,
+        )
+        .map_err(to_py_err)?
+        .map_or(env::Value::Null, env::Value::String),
+        (None, Some(stored)) => stored.clone(),
+        (None, None) => return Ok(default.unwrap_or_else(|| py.None())),
+    };
+    to_py(py, &value)
+}
+
+#[pyfunction]
+#[pyo3(signature = (name, profile=None))]
+fn require(name: String, profile: Option<String>) -> PyResult<String> {
+    lookup(&name, profile.as_deref())?.ok_or_else(|| {
+        PyErr::new::<pyo3::exceptions::PyKeyError, _>(format!(
+            "Required environment variable '{}' is not set.",
+            name
+        ))
+    })
+}
+
+/// The names of the stored variables, flattened as `externkit run` exports
+/// them.
+fn stored_keys(profile: Option<&str>) -> PyResult<Vec<String>> {
+    let env_vars = load_env_vars(profile)?;
+    let mut keys: Vec<String> = env::value::flatten(&env_vars)
+        .into_iter()
+        .filter(|(_, value)| !value.is_null())
+        .map(|(key, _)| key)
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// The stored variables with references and secrets resolved, as
+/// `externkit run` exports them.
+fn exported_vars(profile: Option<&str>) -> PyResult<std::collections::BTreeMap<String, String>> {
+    let (env_vars, shared_keys) = load_env_vars_with_shared_keys(profile)?;
+    env::interpolate::Interpolator::new(&env::value::flatten(&env_vars))
+        .secrets(env::secrets::registry())
+        .untrusted(&shared_keys)
+        .resolve_all()
+        .map(|vars| vars.into_iter().collect())
+        .map_err(to_py_err)
+}
+
+/// Opens `profile` for editing. Waits for the project lock with the GIL
+/// released, so other threads keep running while another process or thread
+/// holds it.
+fn open_store(py: Python<'_>, profile: Option<&str>) -> PyResult<env::Store> {
+    let Some(project_dir) = project::locate(None) else {
+        return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+            "Project not initialized. Use the `externkit init` command to set up the project.",
+        ));
+    };
+    py.allow_threads(|| {
+        env::Store::load_locked(&project_dir, profile, env::crypto::KeySource::from_env())
+    })
+    .map_err(to_py_err)
+}
+
+/// Saves like the CLI does: a failure to update the audit log is only a
+/// warning.
+fn save_store(py: Python<'_>, store: &mut env::Store, action: &str) -> PyResult<()> {
+    let result = store.save(action);
+    ENV_CACHE.lock().unwrap().invalidate();
+    match result {
+        Ok(()) => Ok(()),
+        Err(e @ env::Error::Audit(_)) => PyErr::warn(
+            py,
+            &py.get_type::<pyo3::exceptions::PyRuntimeWarning>(),
+            &std::ffi::CString::new(e.to_string())?,
+            1,
+        ),
+        Err(e) => Err(to_py_err(e)),
+    }
+}
+
+/// Strings are stored as they are, anything else as the JSON `json.dumps`
+/// produces for it.
+fn to_value(py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<env::Value> {
+    if let Ok(value) = value.extract::<String>() {
+        return Ok(env::Value::String(value));
+    }
+    let json: String = py
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    match serde_json::from_str(&json) {
+        Ok(env::Value::Null) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Use externkit.env.delete() to remove an environment variable.",
+        )),
+        Ok(value) => Ok(value),
+        Err(e) => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            e.to_string(),
+        )),
+    }
+}
+
+fn missing_key(name: &str) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyKeyError, _>(name.to_string())
+}
+
+/// Fails for a `PARENT__CHILD` name that only exists because `PARENT` is a
+/// nested object: writing it would add a variable next to the object, and
+/// there is no such variable to remove.
+fn reject_flattened(name: &str, profile: Option<&str>) -> PyResult<()> {
+    let env_vars = load_env_vars(profile)?;
+    if env_vars.contains_key(name) {
+        return Ok(());
+    }
+    let parent = env_vars.iter().find(|(key, value)| {
+        value.is_object()
+            && name
+                .strip_prefix(key.as_str())
+                .is_some_and(|rest| rest.starts_with(env::value::NESTED_SEPARATOR))
+    });
+    match parent {
+        Some((parent, _)) => Err(PyErr::new::<pyo3::exceptions::PyKeyError, _>(format!(
+            "'{}' is part of the nested variable '{}'; change '{}' instead.",
+            name, parent, parent
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Stores `value` under `name` in the active profile, or `profile`,
+/// through the same locked and audited store as `externkit env set`.
+#[pyfunction]
+#[pyo3(signature = (name, value, profile=None))]
+fn set(
+    py: Python<'_>,
+    name: String,
+    value: &Bound<'_, PyAny>,
+    profile: Option<String>,
+) -> PyResult<()> {
+    if name.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Environment variable name cannot be empty.",
+        ));
+    }
+    let value = to_value(py, value)?;
+    if value.as_str() == Some("") {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "Environment variable value cannot be empty.",
+        ));
+    }
+    let mut store = open_store(py, profile.as_deref())?;
+    if store.get(&name) == Some(&value) {
+        return Ok(());
     }
+    store.set(name.clone(), value);
+    store.check_references([name.as_str()]).map_err(to_py_err)?;
+    save_store(py, &mut store, "set")
 }
 
+/// Removes `name` from the active profile, or `profile`. Raises KeyError
+/// if the profile does not store it.
 #[pyfunction]
-fn get(env_name: String) -> Option<String> {
-    match std::env::var(&env_name) {
-        Ok(val) => Some(val),
-        Err(_) => load_env_vars().get(&env_name).cloned(),
+#[pyo3(signature = (name, profile=None))]
+fn delete(py: Python<'_>, name: String, profile: Option<String>) -> PyResult<()> {
+    let mut store = open_store(py, profile.as_deref())?;
+    if store.remove(&name).is_none() {
+        return Err(missing_key(&name));
     }
+    save_store(py, &mut store, "unset")
+}
+
+/// Copies the stored variables into `os.environ` so libraries reading it see
+/// them. Variables already set are kept unless `override` is true. Returns
+/// the names that were set.
+#[pyfunction]
+#[pyo3(signature = (r#override=false, profile=None))]
+fn load_into_os(
+    py: Python<'_>,
+    r#override: bool,
+    profile: Option<String>,
+) -> PyResult<Vec<String>> {
+    let environ = py.import("os")?.getattr("environ")?;
+    let mut loaded = Vec::new();
+    for (key, value) in exported_vars(profile.as_deref())? {
+        if r#override || !environ.contains(&key)? {
+            environ.set_item(&key, value)?;
+            loaded.push(key);
+        }
+    }
+    Ok(loaded)
+}
+
+/// The stored variables of a profile as a mapping. Keys are the stored
+/// names, flattened as `externkit run` exports them; values are read like
+/// `get`, so the OS environment wins. Assigning and deleting keys writes to
+/// the store, except for the flattened names of nested objects. It is not a
+/// `MutableMapping`, as those names could not be written back.
+#[pyclass(module = "externkit.env", mapping)]
+struct EnvStore {
+    profile: Option<String>,
+}
+
+#[pymethods]
+impl EnvStore {
+    fn __getitem__(&self, name: &str) -> PyResult<String> {
+        if !stored_keys(self.profile.as_deref())?
+            .iter()
+            .any(|key| key == name)
+        {
+            return Err(missing_key(name));
+        }
+        lookup(name, self.profile.as_deref())?.ok_or_else(|| missing_key(name))
+    }
+
+    fn __setitem__(&self, py: Python<'_>, name: String, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        reject_flattened(&name, self.profile.as_deref())?;
+        set(py, name, value, self.profile.clone())
+    }
+
+    fn __delitem__(&self, py: Python<'_>, name: String) -> PyResult<()> {
+        reject_flattened(&name, self.profile.as_deref())?;
+        delete(py, name, self.profile.clone())
+    }
+
+    fn __contains__(&self, name: &str) -> PyResult<bool> {
+        Ok(stored_keys(self.profile.as_deref())?
+            .iter()
+            .any(|key| key == name))
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        Ok(stored_keys(self.profile.as_deref())?.len())
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyIterator>> {
+        pyo3::types::PyList::new(py, stored_keys(self.profile.as_deref())?)?.try_iter()
+    }
+
+    fn keys(&self) -> PyResult<Vec<String>> {
+        stored_keys(self.profile.as_deref())
+    }
+
+    fn values(&self) -> PyResult<Vec<String>> {
+        Ok(self.items()?.into_iter().map(|(_, value)| value).collect())
+    }
+
+    fn items(&self) -> PyResult<Vec<(String, String)>> {
+        Ok(exported_vars(self.profile.as_deref())?
+            .into_iter()
+            .map(|(key, value)| {
+                let value = std::env::var(&key).unwrap_or(value);
+                (key, value)
+            })
+            .collect())
+    }
+
+    #[pyo3(signature = (name, default=None))]
+    fn get(&self, py: Python<'_>, name: &str, default: Option<PyObject>) -> PyResult<PyObject> {
+        use pyo3::IntoPyObjectExt;
+
+        match self.__getitem__(name) {
+            Ok(value) => value.into_py_any(py),
+            Err(e) if e.is_instance_of::<pyo3::exceptions::PyKeyError>(py) => {
+                Ok(default.unwrap_or_else(|| py.None()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("<externkit.env.EnvStore profile={:?}>", profile),
+            None => "<externkit.env.EnvStore>".to_string(),
+        }
+    }
+}
+
+/// The stored variables of the active profile, or `profile`, as a mapping.
+#[pyfunction]
+#[pyo3(signature = (profile=None))]
+fn all(profile: Option<String>) -> EnvStore {
+    EnvStore { profile }
+}
+
+/// Drops cached variables and secrets so the next read goes back to disk.
+#[pyfunction]
+fn reload() {
+    ENV_CACHE.lock().unwrap().invalidate();
+    env::secrets::registry().clear_cache();
+}
+
+/// A secret resolver implemented by a Python callable.
+struct PyResolver {
+    prefix: String,
+    callback: PyObject,
+}
+
+impl env::secrets::Resolver for PyResolver {
+    fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String, String> {
+        Python::with_gil(|py| {
+            self.callback
+                .call1(py, (reference,))
+                .and_then(|secret| secret.extract::<String>(py))
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// Resolves stored values starting with `secret+` and `prefix` by calling
+/// `callback` with the rest of the value. Replaces the resolver registered
+/// for the same prefix, including the built-in `file://`, `cmd:` and
+/// `keyring:` ones.
+#[pyfunction]
+fn register_resolver(py: Python<'_>, prefix: String, callback: PyObject) -> PyResult<()> {
+    if !callback.bind(py).is_callable() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "register_resolver() expects a callable.",
+        ));
+    }
+    if prefix.is_empty() {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "The resolver prefix cannot be empty.",
+        ));
+    }
+    env::secrets::registry().register(std::sync::Arc::new(PyResolver { prefix, callback }));
+    Ok(())
+}
+
+/// Keeps resolved secrets for `seconds`, forever when `None`. Zero disables
+/// caching.
+#[pyfunction]
+#[pyo3(signature = (seconds))]
+fn set_secret_cache_ttl(seconds: Option<f64>) -> PyResult<()> {
+    let ttl = seconds
+        .map(std::time::Duration::try_from_secs_f64)
+        .transpose()
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    env::secrets::registry().set_ttl(ttl);
+    Ok(())
+}
+
+/// Registers `callback` to be called with the sorted list of changed keys
+/// whenever the active profile is edited. A key counts as changed when its
+/// value with references expanded does. Returns the callback so it can be
+/// used as a decorator.
+#[pyfunction]
+fn on_change(py: Python<'_>, callback: PyObject) -> PyResult<PyObject> {
+    if !callback.bind(py).is_callable() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "on_change() expects a callable.",
+        ));
+    }
+
+    let mut watcher = WATCHER.lock().unwrap();
+    let start = watcher.is_none();
+    watcher
+        .get_or_insert_with(Vec::new)
+        .push(callback.clone_ref(py));
+    drop(watcher);
+
+    if start {
+        // A daemon Python thread rather than a Rust one, so interpreter
+        // shutdown never races a thread trying to take the GIL.
+        let kwargs = pyo3::types::PyDict::new(py);
+        kwargs.set_item("target", wrap_pyfunction!(watch_env_vars, py)?)?;
+        kwargs.set_item("name", "externkit-env-watcher")?;
+        kwargs.set_item("daemon", true)?;
+        let thread = py
+            .import("threading")?
+            .getattr("Thread")?
+            .call((), Some(&kwargs))?;
+        thread.call_method0("start")?;
+        py.import("atexit")?
+            .call_method1("register", (wrap_pyfunction!(stop_watcher, py)?, thread))?;
+    }
+    Ok(callback)
+}
+
+/// The values the watcher compares: resolved, so editing `A` also reports a
+/// `B` stored as `${A}`. Secret references are compared as written, as
+/// resolving them on every poll could run commands. Falls back to the
+/// stored values when a reference does not resolve.
+fn watched_values(
+    env_vars: &std::collections::HashMap<String, env::Value>,
+) -> std::collections::HashMap<String, String> {
+    env::interpolate::resolve_nested(env_vars).unwrap_or_else(|_| {
+        env_vars
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), env::value::as_env_string(value)?)))
+            .collect()
+    })
+}
+
+#[pyfunction]
+fn watch_env_vars(py: Python<'_>) {
+    let mut previous_vars = load_env_vars(None).unwrap_or_default();
+    let mut previous = watched_values(&previous_vars);
+    let (stop, wake) = &WATCHER_STOP;
+    loop {
+        let stopped = py.allow_threads(|| {
+            let stopped = stop.lock().unwrap();
+            *wake
+                .wait_timeout_while(stopped, WATCH_INTERVAL, |stopped| !*stopped)
+                .unwrap()
+                .0
+        });
+        if stopped {
+            return;
+        }
+        let Ok(current_vars) = load_env_vars(None) else {
+            continue;
+        };
+        if std::sync::Arc::ptr_eq(&previous_vars, &current_vars) {
+            continue;
+        }
+        let current = watched_values(&current_vars);
+
+        let mut changed: Vec<&String> = current
+            .iter()
+            .filter(|(key, value)| previous.get(*key) != Some(*value))
+            .map(|(key, _)| key)
+            .chain(previous.keys().filter(|key| !current.contains_key(*key)))
+            .collect();
+        changed.sort();
+        if !changed.is_empty() {
+            let callbacks: Vec<PyObject> = WATCHER
+                .lock()
+                .unwrap()
+                .iter()
+                .flatten()
+                .map(|callback| callback.clone_ref(py))
+                .collect();
+            for callback in callbacks {
+                if let Err(e) = callback.call1(py, (changed.clone(),)) {
+                    e.write_unraisable(py, Some(callback.bind(py)));
+                }
+            }
+        }
+        previous_vars = current_vars;
+        previous = current;
+    }
+}
+
+/// Registered with `atexit`: ends the watcher and waits for it, so it never
+/// takes the GIL again while the interpreter is finalized. A daemon thread
+/// doing that is ended by unwinding through the Rust frames on its stack,
+/// which aborts the process.
+#[pyfunction]
+fn stop_watcher(thread: &Bound<'_, PyAny>) -> PyResult<()> {
+    *WATCHER_STOP.0.lock().unwrap() = true;
+    WATCHER_STOP.1.notify_all();
+    thread.call_method0("join")?;
+    Ok(())
 }
 
 #[pymodule]
@@ -25,7 +670,35 @@ fn externkit(m: &Bound<'_, PyModule>) -> PyResult<()> {
     let env_module = PyModule::new(m.py(), "env")?;
     let sqlite3_module = PyModule::new(m.py(), "sqlite3")?;
     sqlite3_module.add_class::<sqlite3::SqliteClient>()?;
+    sqlite3_module.add_class::<sqlite3::Row>()?;
+    sqlite3_module.add_class::<sqlite3::Cursor>()?;
+    sqlite3_module.add_class::<sqlite3::Transaction>()?;
+    sqlite3_module.add_function(wrap_pyfunction!(
+        sqlite3::register_adapter,
+        &sqlite3_module
+    )?)?;
     env_module.add_function(wrap_pyfunction!(get, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(get_int, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(get_float, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(get_bool, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(get_list, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(get_json, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(get_typed, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(require, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(reload, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(on_change, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(set, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(delete, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(load_into_os, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(all, &env_module)?)?;
+    env_module.add_class::<EnvStore>()?;
+    env_module.add("store", EnvStore { profile: None })?;
+    m.py()
+        .import("collections.abc")?
+        .getattr("Mapping")?
+        .call_method1("register", (env_module.getattr("EnvStore")?,))?;
+    env_module.add_function(wrap_pyfunction!(register_resolver, &env_module)?)?;
+    env_module.add_function(wrap_pyfunction!(set_secret_cache_ttl, &env_module)?)?;
     m.add_submodule(&env_module)?;
     m.add_submodule(&sqlite3_module)?;
     Ok(())
@@ -25,11 +25,32 @@ fn main() {
                 .valid(clap::builder::styling::AnsiColor::Green.on_default().bold())
                 .invalid(clap::builder::styling::AnsiColor::Red.on_default().bold()),
         )
+        .arg(
+            Arg::new("project")
+                .long("project")
+                .global(true)
+                .help("Project root to use instead of searching parent directories")
+                .value_parser(value_parser!(String)),
+        )
         .subcommand(
             Command::new("env")
                 .about("Environment variables management commands")
                 .subcommand_required(true)
                 .arg_required_else_help(true)
+                .arg(
+                    Arg::new("keyfile")
+                        .long("keyfile")
+                        .global(true)
+                        .help("Keyfile used to encrypt or decrypt the environment variables")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .global(true)
+                        .help("Profile to operate on instead of the active one")
+                        .value_parser(value_parser!(String)),
+                )
                 .subcommand(
                     Command::new("add")
                         .about("Add or set an environment variable")
@@ -44,6 +65,13 @@ fn main() {
                                 .help("Environment variable value")
                                 .required(true)
                                 .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("type")
+                                .long("type")
+                                .help("Store the value as this JSON type instead of a string")
+                                .default_value("string")
+                                .value_parser(["string", "int", "bool", "json"]),
                         ),
                 )
                 .subcommand(
@@ -70,10 +98,358 @@ fn main() {
                                 .help("New environment variable value")
                                 .required(true)
                                 .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("type")
+                                .long("type")
+                                .help("Store the value as this JSON type instead of a string")
+                                .default_value("string")
+                                .value_parser(["string", "int", "bool", "json"]),
+                        ),
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Add or update environment variables in a single write")
+                        .arg(
+                            Arg::new("pairs")
+                                .help("KEY=VALUE pairs, or a single KEY with --value-stdin")
+                                .num_args(0..)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("from_stdin")
+                                .long("from-stdin")
+                                .help("Also read KEY=VALUE lines from stdin")
+                                .action(clap::ArgAction::SetTrue)
+                                .conflicts_with("value_stdin"),
+                        )
+                        .arg(
+                            Arg::new("value_stdin")
+                                .long("value-stdin")
+                                .help("Read the value of KEY from stdin, keeping it out of the shell history")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("type")
+                                .long("type")
+                                .help("Store the values as this JSON type instead of strings")
+                                .default_value("string")
+                                .value_parser(["string", "int", "bool", "json"]),
+                        ),
+                )
+                .subcommand(
+                    Command::new("unset")
+                        .about("Remove environment variables in a single write")
+                        .arg(
+                            Arg::new("keys")
+                                .help("Environment variable names")
+                                .required(true)
+                                .num_args(1..)
+                                .value_parser(value_parser!(String)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List environment variables with their values masked")
+                        .arg(
+                            Arg::new("filter")
+                                .help("Only list keys matching this glob or prefix")
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("reveal")
+                                .long("reveal")
+                                .help("Show values instead of masking them")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("raw")
+                                .long("raw")
+                                .help("Show values without expanding ${NAME} references")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .help("Output format")
+                                .default_value("table")
+                                .value_parser(["table", "json", "dotenv"]),
+                        ),
+                )
+                .subcommand(
+                    Command::new("show")
+                        .about("Print the raw value of a single environment variable")
+                        .arg(
+                            Arg::new("key")
+                                .help("Environment variable name")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("raw")
+                                .long("raw")
+                                .help("Print the value without expanding ${NAME} references")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Import variables from a dotenv file")
+                        .arg(
+                            Arg::new("file")
+                                .help("Dotenv file to read")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("mode")
+                                .long("mode")
+                                .help("How to combine the file with existing variables")
+                                .default_value("merge")
+                                .value_parser(["merge", "overwrite", "skip-existing"]),
+                        )
+                        .arg(
+                            Arg::new("dry_run")
+                                .long("dry-run")
+                                .help("Show the changes without applying them")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export variables to a dotenv file")
+                        .arg(
+                            Arg::new("file")
+                                .help("Dotenv file to write, or `-` for stdout")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("dry_run")
+                                .long("dry-run")
+                                .help("Show the changes without writing the file")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("check")
+                        .about("Validate environment variables against .externkit/env.schema.json"),
+                )
+                .subcommand(
+                    Command::new("doctor")
+                        .about("Detect and repair damaged environment variable stores")
+                        .arg(
+                            Arg::new("dry_run")
+                                .long("dry-run")
+                                .help("Report the problems without repairing them")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("restore")
+                        .about("Restore the environment variables from a backup")
+                        .arg(
+                            Arg::new("backup")
+                                .help("Backup to restore, 1 being the newest")
+                                .value_parser(value_parser!(usize)),
+                        )
+                        .arg(
+                            Arg::new("list")
+                                .long("list")
+                                .help("List available backups instead of restoring one")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("history")
+                        .about("Show the recorded changes to the environment variables")
+                        .arg(
+                            Arg::new("key")
+                                .help("Only show changes to this environment variable")
+                                .value_parser(value_parser!(String)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("revert")
+                        .about("Set an environment variable back to its value at a history entry")
+                        .arg(
+                            Arg::new("key")
+                                .help("Environment variable name")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .help("History entry to revert to")
+                                .required(true)
+                                .value_parser(value_parser!(u64)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("lock")
+                        .about("Encrypt the environment variables with a passphrase or keyfile"),
+                )
+                .subcommand(
+                    Command::new("unlock")
+                        .about("Decrypt the environment variables back to plaintext"),
+                )
+                .subcommand(
+                    Command::new("use")
+                        .about("Switch the active profile, or list profiles when none is given")
+                        .arg(
+                            Arg::new("name")
+                                .help("Profile to switch to; created if it does not exist")
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("extends")
+                                .long("extends")
+                                .help("Base profile to inherit variables from")
+                                .value_parser(value_parser!(String)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("diff")
+                        .about("Compare two profiles, dotenv or JSON store files, or `@os`")
+                        .arg(
+                            Arg::new("a")
+                                .help("Profile, file or `@os` to compare from")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("b")
+                                .help("Profile, file or `@os` to compare to")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("reveal")
+                                .long("reveal")
+                                .help("Show values instead of masking them")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("sync")
+                        .about("Copy missing and changed variables from one profile or file to another")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .help("Profile, file or `@os` to copy from")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .help("Profile or file to copy to")
+                                .required(true)
+                                .value_parser(value_parser!(String)),
+                        )
+                        .arg(
+                            Arg::new("prune")
+                                .long("prune")
+                                .help("Also remove variables the source does not have")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("yes")
+                                .long("yes")
+                                .short('y')
+                                .help("Apply the changes without asking")
+                                .action(clap::ArgAction::SetTrue),
+                        )
+                        .arg(
+                            Arg::new("reveal")
+                                .long("reveal")
+                                .help("Show values instead of masking them")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("print")
+                        .about("Print shell statements exporting the environment variables, for `eval`")
+                        .arg(
+                            Arg::new("shell")
+                                .long("shell")
+                                .help("Shell to print the statements for")
+                                .default_value("bash")
+                                .value_parser(["bash", "zsh", "fish", "powershell"]),
+                        )
+                        .arg(
+                            Arg::new("hook")
+                                .long("hook")
+                                .help("Print what the shell hook evaluates on a directory change")
+                                .hide(true)
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                )
+                .subcommand(
+                    Command::new("allow")
+                        .about("Trust the project's shared variables and secrets in the shell hook"),
+                )
+                .subcommand(
+                    Command::new("deny").about("Stop trusting the project in the shell hook"),
+                )
+                .subcommand(
+                    Command::new("shell-hook")
+                        .about("Print a hook that loads the environment variables when entering the project")
+                        .arg(
+                            Arg::new("shell")
+                                .help("Shell to print the hook for")
+                                .required(true)
+                                .value_parser(["bash", "zsh", "fish"]),
                         ),
                 ),
         )
         .subcommand(Command::new("init").about("Initialize the externkit project"))
+        .subcommand(
+            Command::new("run")
+                .about("Run a command with the stored environment variables injected")
+                .arg(
+                    Arg::new("profile")
+                        .long("profile")
+                        .help("Profile to load instead of the active one")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("keyfile")
+                        .long("keyfile")
+                        .help("Keyfile used to decrypt the environment variables")
+                        .value_parser(value_parser!(String)),
+                )
+                .arg(
+                    Arg::new("override")
+                        .long("override")
+                        .help("Stored values replace variables already set in the OS environment")
+                        .action(clap::ArgAction::SetTrue)
+                        .overrides_with("no_override"),
+                )
+                .arg(
+                    Arg::new("no_override")
+                        .long("no-override")
+                        .help("Variables already set in the OS environment win (default)")
+                        .action(clap::ArgAction::SetTrue)
+                        .overrides_with("override"),
+                )
+                .arg(
+                    Arg::new("clear_env")
+                        .long("clear-env")
+                        .help("Start from an empty environment instead of inheriting it")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("command")
+                        .help("Command to run, usually given after `--`")
+                        .required(true)
+                        .num_args(1..)
+                        .trailing_var_arg(true)
+                        .allow_hyphen_values(true)
+                        .value_parser(value_parser!(String)),
+                ),
+        )
         .subcommand(
             Command::new("get_pip")
                 .about("Fetch and run get-pip.py script")
@@ -95,10 +471,12 @@ fn main() {
         );
 
     let matches = matches.get_matches();
-    let project_path = std::path::PathBuf::from("./.externkit");
+    let project_arg = matches
+        .get_one::<String>("project")
+        .map(std::path::Path::new);
     match matches.subcommand() {
         Some(("init", _)) => {
-            backend::utils::init_project();
+            backend::utils::init_project(&externkit::project::init_dir(project_arg));
         }
         Some(("get_pip", sub_matches)) => {
             backend::python_tools::get_pip(
@@ -113,9 +491,36 @@ fn main() {
                 eprintln!("Editor error: {}", e);
             }
         }
+        Some(("env", env_matches)) if env_matches.subcommand_name() == Some("shell-hook") => {
+            let sub_matches = env_matches
+                .subcommand_matches("shell-hook")
+                .expect("matched");
+            let shell = sub_matches.get_one::<String>("shell").expect("required");
+
+            backend::shell::print_hook(backend::shell::Shell::from_name(shell).expect("validated"));
+        }
+        // The hook also runs outside of projects, where it only unloads.
+        Some(("env", env_matches))
+            if env_matches
+                .subcommand_matches("print")
+                .is_some_and(|sub_matches| sub_matches.get_flag("hook")) =>
+        {
+            let sub_matches = env_matches.subcommand_matches("print").expect("matched");
+            let shell = sub_matches.get_one::<String>("shell").expect("default");
+            let options = externkit::project::locate(project_arg)
+                .filter(|dir| dir.is_dir())
+                .map(|dir| backend::env::StoreOptions::from_matches(sub_matches, &dir));
+
+            backend::shell::print_hook_env(
+                backend::shell::Shell::from_name(shell).expect("validated"),
+                options,
+            );
+        }
         Some((cmd, _)) if cmd == "help" || cmd == "version" => {}
         _ => {
-            if !project_path.exists() {
+            let Some(project_dir) =
+                externkit::project::locate(project_arg).filter(|dir| dir.is_dir())
+            else {
                 println!(
                     "{}",
                     colored::Colorize::red(
@@ -123,11 +528,26 @@ fn main() {
                     )
                 );
                 return;
-            }
+            };
 
             match matches.subcommand() {
                 Some(("env", env_matches)) => {
-                    handle_env_var_command(env_matches);
+                    handle_env_var_command(env_matches, &project_dir);
+                }
+                Some(("run", run_matches)) => {
+                    let command: Vec<String> = run_matches
+                        .get_many::<String>("command")
+                        .expect("required")
+                        .cloned()
+                        .collect();
+                    let run_options = backend::run::RunOptions {
+                        override_os: run_matches.get_flag("override"),
+                        clear_env: run_matches.get_flag("clear_env"),
+                    };
+                    let options =
+                        backend::env::StoreOptions::from_matches(run_matches, &project_dir);
+
+                    backend::run::run_command(&command, &run_options, &options);
                 }
                 _ => unreachable!(
                     "Exhausted list of subcommands and subcommand_required prevents `None`"
@@ -137,24 +557,171 @@ fn main() {
     }
 }
 
-fn handle_env_var_command(matches: &ArgMatches) {
+fn handle_env_var_command(matches: &ArgMatches, project_dir: &std::path::Path) {
     match matches.subcommand() {
         Some(("add", sub_matches)) => {
             let key = sub_matches.get_one::<String>("key").expect("required");
             let value = sub_matches.get_one::<String>("value").expect("required");
+            let value_type = sub_matches.get_one::<String>("type").expect("default");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
 
-            backend::env::add_env_var(key, value);
+            backend::env::add_env_var(key, value, value_type, &options);
         }
         Some(("delete", sub_matches)) => {
             let key = sub_matches.get_one::<String>("key").expect("required");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
 
-            backend::env::delete_env_var(key);
+            backend::env::delete_env_var(key, &options);
         }
         Some(("update", sub_matches)) => {
             let key = sub_matches.get_one::<String>("key").expect("required");
             let value = sub_matches.get_one::<String>("value").expect("required");
+            let value_type = sub_matches.get_one::<String>("type").expect("default");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::update_env_var(key, value, value_type, &options);
+        }
+        Some(("set", sub_matches)) => {
+            let pairs: Vec<String> = sub_matches
+                .get_many::<String>("pairs")
+                .unwrap_or_default()
+                .cloned()
+                .collect();
+            let input = if sub_matches.get_flag("value_stdin") {
+                backend::env::SetInput::Value
+            } else if sub_matches.get_flag("from_stdin") {
+                backend::env::SetInput::Lines
+            } else {
+                backend::env::SetInput::Args
+            };
+            let value_type = sub_matches.get_one::<String>("type").expect("default");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::set_env_vars(&pairs, input, value_type, &options);
+        }
+        Some(("unset", sub_matches)) => {
+            let keys: Vec<String> = sub_matches
+                .get_many::<String>("keys")
+                .expect("required")
+                .cloned()
+                .collect();
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::unset_env_vars(&keys, &options);
+        }
+        Some(("list", sub_matches)) => {
+            let filter = sub_matches.get_one::<String>("filter");
+            let reveal = sub_matches.get_flag("reveal");
+            let raw = sub_matches.get_flag("raw");
+            let format = sub_matches.get_one::<String>("format").expect("default");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::list_env_vars(filter, reveal, raw, format, &options);
+        }
+        Some(("show", sub_matches)) => {
+            let key = sub_matches.get_one::<String>("key").expect("required");
+            let raw = sub_matches.get_flag("raw");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::show_env_var(key, raw, &options);
+        }
+        Some(("import", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").expect("required");
+            let mode = sub_matches.get_one::<String>("mode").expect("default");
+            let dry_run = sub_matches.get_flag("dry_run");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::import_env_file(file, mode, dry_run, &options);
+        }
+        Some(("export", sub_matches)) => {
+            let file = sub_matches.get_one::<String>("file").expect("required");
+            let dry_run = sub_matches.get_flag("dry_run");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::export_env_file(file, dry_run, &options);
+        }
+        Some(("check", sub_matches)) => {
+            backend::env::check_env_vars(&backend::env::StoreOptions::from_matches(
+                sub_matches,
+                project_dir,
+            ));
+        }
+        Some(("doctor", sub_matches)) => {
+            let dry_run = sub_matches.get_flag("dry_run");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::doctor_env_vars(dry_run, &options);
+        }
+        Some(("restore", sub_matches)) => {
+            let backup = sub_matches.get_one::<usize>("backup").copied();
+            let list = sub_matches.get_flag("list");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::restore_env_vars(backup, list, &options);
+        }
+        Some(("history", sub_matches)) => {
+            let key = sub_matches.get_one::<String>("key");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::show_history(key, &options);
+        }
+        Some(("revert", sub_matches)) => {
+            let key = sub_matches.get_one::<String>("key").expect("required");
+            let entry = *sub_matches.get_one::<u64>("to").expect("required");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::env::revert_env_var(key, entry, &options);
+        }
+        Some(("lock", sub_matches)) => {
+            backend::env::lock_env_vars(&backend::env::StoreOptions::from_matches(
+                sub_matches,
+                project_dir,
+            ));
+        }
+        Some(("unlock", sub_matches)) => {
+            backend::env::unlock_env_vars(&backend::env::StoreOptions::from_matches(
+                sub_matches,
+                project_dir,
+            ));
+        }
+        Some(("diff", sub_matches)) => {
+            let a = sub_matches.get_one::<String>("a").expect("required");
+            let b = sub_matches.get_one::<String>("b").expect("required");
+            let reveal = sub_matches.get_flag("reveal");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::sync::diff_env_vars(a, b, reveal, &options);
+        }
+        Some(("sync", sub_matches)) => {
+            let from = sub_matches.get_one::<String>("from").expect("required");
+            let to = sub_matches.get_one::<String>("to").expect("required");
+            let prune = sub_matches.get_flag("prune");
+            let yes = sub_matches.get_flag("yes");
+            let reveal = sub_matches.get_flag("reveal");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::sync::sync_env_vars(from, to, prune, yes, reveal, &options);
+        }
+        Some(("print", sub_matches)) => {
+            let shell = sub_matches.get_one::<String>("shell").expect("default");
+            let options = backend::env::StoreOptions::from_matches(sub_matches, project_dir);
+
+            backend::shell::print_env(
+                backend::shell::Shell::from_name(shell).expect("validated"),
+                &options,
+            );
+        }
+        Some(("allow", _)) => {
+            backend::shell::allow_project(project_dir);
+        }
+        Some(("deny", _)) => {
+            backend::shell::deny_project(project_dir);
+        }
+        Some(("use", sub_matches)) => {
+            let name = sub_matches.get_one::<String>("name");
+            let extends = sub_matches.get_one::<String>("extends");
 
-            backend::env::update_env_var(key, value);
+            backend::env::use_profile(name, extends, project_dir);
         }
         _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
@@ -0,0 +1,42 @@
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_DIR_NAME: &str = ".externkit";
+
+/// Walks up from `start` and returns the nearest `.externkit` directory,
+/// the way git finds `.git`.
+pub fn find_project_dir(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_DIR_NAME))
+        .find(|candidate| candidate.is_dir())
+}
+
+/// Returns the `.externkit` directory to use. An explicit project root wins,
+/// then `EXTERNKIT_PROJECT_DIR`, then the nearest `.externkit` above the
+/// working directory. Explicit roots may also point at `.externkit` itself.
+pub fn locate(explicit: Option<&Path>) -> Option<PathBuf> {
+    match configured_root(explicit) {
+        Some(root) => Some(project_dir_in(root)),
+        None => find_project_dir(&std::env::current_dir().ok()?),
+    }
+}
+
+/// Returns where `externkit init` should create the project: like `locate`,
+/// but without searching parent directories.
+pub fn init_dir(explicit: Option<&Path>) -> PathBuf {
+    project_dir_in(configured_root(explicit).unwrap_or_else(|| PathBuf::from(".")))
+}
+
+fn configured_root(explicit: Option<&Path>) -> Option<PathBuf> {
+    explicit
+        .map(Path::to_path_buf)
+        .or_else(|| std::env::var_os("EXTERNKIT_PROJECT_DIR").map(PathBuf::from))
+}
+
+fn project_dir_in(root: PathBuf) -> PathBuf {
+    if root.file_name() == Some(PROJECT_DIR_NAME.as_ref()) {
+        root
+    } else {
+        root.join(PROJECT_DIR_NAME)
+    }
+}
@@ -1,88 +1,696 @@
 use pyo3::prelude::*;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDict, PyFloat, PyInt, PyMemoryView, PyString, PyTuple, PyType,
+};
 
+use std::collections::HashMap;
+use std::mem::ManuallyDrop;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
+use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
+
+use rusqlite::types::Value;
+
+/// Adapters registered with `register_adapter`, checked in order before the
+/// built-in conversions.
+static ADAPTERS: Mutex<Vec<(Py<PyType>, PyObject)>> = Mutex::new(Vec::new());
+
+/// Converts `obj` to SQLite with `adapter` when one is registered for its
+/// type, which must return something the built-in conversions accept.
+/// Called like Python's `sqlite3.register_adapter`.
+#[pyfunction]
+pub fn register_adapter(py: Python<'_>, r#type: Py<PyType>, adapter: PyObject) -> PyResult<()> {
+    if !adapter.bind(py).is_callable() {
+        return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+            "register_adapter() expects a callable.",
+        ));
+    }
+    let replaced = {
+        let mut adapters = ADAPTERS.lock().unwrap();
+        let replaced = adapters
+            .iter()
+            .position(|(existing, _)| existing.bind(py).is(r#type.bind(py)))
+            .map(|index| adapters.remove(index));
+        adapters.push((r#type, adapter));
+        replaced
+    };
+    // Dropping the old adapter can run Python code, which must not happen
+    // while `ADAPTERS` is locked.
+    drop(replaced);
+    Ok(())
+}
+
+/// Finds the adapter for `obj`: one registered for its exact type first,
+/// then one for a base class. The adapters are copied out of `ADAPTERS`
+/// first, as `isinstance` can run Python code that registers adapters too.
+fn find_adapter(obj: &Bound<'_, PyAny>) -> PyResult<Option<PyObject>> {
+    let py = obj.py();
+    let adapters: Vec<(Py<PyType>, PyObject)> = ADAPTERS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(t, adapter)| (t.clone_ref(py), adapter.clone_ref(py)))
+        .collect();
+    let type_ = obj.get_type();
+    if let Some((_, adapter)) = adapters.iter().find(|(t, _)| t.bind(py).is(&type_)) {
+        return Ok(Some(adapter.clone_ref(py)));
+    }
+    for (t, adapter) in adapters.iter() {
+        if obj.is_instance(t.bind(py))? {
+            return Ok(Some(adapter.clone_ref(py)));
+        }
+    }
+    Ok(None)
+}
+
+/// Binds a Python object with the matching SQLite type. Dates and times are
+/// stored as ISO 8601 text and `Decimal` as text, so no precision is lost.
+pub(crate) fn to_sql(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    if let Some(adapter) = find_adapter(obj)? {
+        let adapted = adapter.bind(obj.py()).call1((obj,))?;
+        return builtin_to_sql(&adapted);
+    }
+    builtin_to_sql(obj)
+}
+
+fn builtin_to_sql(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let py = obj.py();
+    if obj.is_none() {
+        return Ok(Value::Null);
+    }
+    // `bool` is a subclass of `int`, so it has to come first.
+    if obj.is_instance_of::<PyBool>() {
+        return Ok(Value::Integer(obj.extract::<bool>()? as i64));
+    }
+    if obj.is_instance_of::<PyInt>() {
+        return obj.extract::<i64>().map(Value::Integer).map_err(|_| {
+            PyErr::new::<pyo3::exceptions::PyOverflowError, _>(
+                "Python int too large to bind as an SQLite INTEGER.",
+            )
+        });
+    }
+    if obj.is_instance_of::<PyFloat>() {
+        return Ok(Value::Real(obj.extract()?));
+    }
+    if obj.is_instance_of::<PyString>() {
+        return Ok(Value::Text(obj.extract()?));
+    }
+    if obj.is_instance_of::<PyBytes>()
+        || obj.is_instance_of::<PyByteArray>()
+        || obj.is_instance_of::<PyMemoryView>()
+    {
+        let bytes = py.import("builtins")?.getattr("bytes")?.call1((obj,))?;
+        return Ok(Value::Blob(bytes.extract()?));
+    }
+
+    let datetime = py.import("datetime")?;
+    // `datetime` is a subclass of `date`, so it has to come first.
+    if obj.is_instance(&datetime.getattr("datetime")?)? {
+        return Ok(Value::Text(
+            obj.call_method1("isoformat", (" ",))?.extract()?,
+        ));
+    }
+    if obj.is_instance(&datetime.getattr("date")?)?
+        || obj.is_instance(&datetime.getattr("time")?)?
+    {
+        return Ok(Value::Text(obj.call_method0("isoformat")?.extract()?));
+    }
+    if obj.is_instance(&py.import("decimal")?.getattr("Decimal")?)? {
+        return Ok(Value::Text(obj.str()?.extract()?));
+    }
+
+    Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(format!(
+        "Unsupported parameter type '{}'. Register an adapter with externkit.sqlite3.register_adapter().",
+        obj.get_type().qualname()?
+    )))
+}
+
+fn to_sql_params(py: Python<'_>, params: &[PyObject]) -> PyResult<Vec<Value>> {
+    params.iter().map(|param| to_sql(param.bind(py))).collect()
+}
+
+/// Locks the connection. While another thread holds it, waits with the GIL
+/// released: that thread may be running Python code, such as the iterable
+/// given to `executemany`, and needs the GIL to finish.
+fn lock<'a>(
+    py: Python<'_>,
+    client: &'a ReentrantMutex<rusqlite::Connection>,
+) -> ReentrantMutexGuard<'a, rusqlite::Connection> {
+    loop {
+        if let Some(conn) = client.try_lock() {
+            return conn;
+        }
+        py.allow_threads(|| drop(client.lock()));
+    }
+}
+
+fn sqlite_err(e: rusqlite::Error) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e))
+}
+
+/// Query parameters: a sequence for `?` placeholders or a dict for `:name`,
+/// `@name` and `$name` ones.
+enum Params {
+    Positional(Vec<Value>),
+    Named(HashMap<String, Value>),
+}
+
+impl Params {
+    fn from_py(params: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let Some(params) = params else {
+            return Ok(Params::Positional(Vec::new()));
+        };
+        if let Ok(dict) = params.downcast::<PyDict>() {
+            let mut named = HashMap::new();
+            for (key, value) in dict.iter() {
+                let key: String = key.extract().map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                        "Named parameters must have str keys.",
+                    )
+                })?;
+                let key = key
+                    .strip_prefix([':', '@', '$'])
+                    .map(str::to_string)
+                    .unwrap_or(key);
+                named.insert(key, to_sql(&value)?);
+            }
+            return Ok(Params::Named(named));
+        }
+        if params.is_instance_of::<PyString>() {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "Parameters must be a list, a tuple or a dict, not a str.",
+            ));
+        }
+        let values = params
+            .try_iter()?
+            .map(|param| to_sql(&param?))
+            .collect::<PyResult<_>>()?;
+        Ok(Params::Positional(values))
+    }
+
+    fn bind(&self, stmt: &mut rusqlite::Statement<'_>) -> PyResult<()> {
+        let count = stmt.parameter_count();
+        match self {
+            Params::Positional(values) => {
+                if values.len() != count {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "The statement uses {} parameter(s), but {} were supplied.",
+                        count,
+                        values.len()
+                    )));
+                }
+                for (index, value) in values.iter().enumerate() {
+                    stmt.raw_bind_parameter(index + 1, value)
+                        .map_err(sqlite_err)?;
+                }
+            }
+            Params::Named(values) => {
+                for index in 1..=count {
+                    let name = match stmt.parameter_name(index) {
+                        Some(name) if !name.starts_with('?') => name.to_string(),
+                        _ => {
+                            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                                "Parameter {} is positional and cannot be bound from a dict. Use :name or @name placeholders.",
+                                index
+                            )))
+                        }
+                    };
+                    let value = values.get(&name[1..]).ok_or_else(|| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "No value supplied for parameter {}.",
+                            name
+                        ))
+                    })?;
+                    stmt.raw_bind_parameter(index, value).map_err(sqlite_err)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How `query` returns each row.
+#[derive(Clone, Copy)]
+enum RowFactory {
+    Tuple,
+    Dict,
+    Row,
+}
+
+impl RowFactory {
+    /// Accepts `"tuple"`, `"dict"` and `"row"` or the `tuple`, `dict` and
+    /// `Row` types themselves.
+    fn from_py(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        let py = obj.py();
+        if let Ok(name) = obj.extract::<String>() {
+            match name.as_str() {
+                "tuple" => return Ok(RowFactory::Tuple),
+                "dict" => return Ok(RowFactory::Dict),
+                "row" => return Ok(RowFactory::Row),
+                _ => {}
+            }
+        } else if obj.is(py.get_type::<PyTuple>()) {
+            return Ok(RowFactory::Tuple);
+        } else if obj.is(py.get_type::<PyDict>()) {
+            return Ok(RowFactory::Dict);
+        } else if obj.is(py.get_type::<Row>()) {
+            return Ok(RowFactory::Row);
+        }
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "row_factory must be 'tuple', 'dict' or 'row'.",
+        ))
+    }
+
+    fn make(
+        self,
+        py: Python<'_>,
+        columns: &Arc<Vec<String>>,
+        values: Vec<PyObject>,
+    ) -> PyResult<PyObject> {
+        match self {
+            RowFactory::Tuple => Ok(PyTuple::new(py, values)?.into_any().unbind()),
+            RowFactory::Dict => {
+                let dict = PyDict::new(py);
+                for (column, value) in columns.iter().zip(values) {
+                    dict.set_item(column, value)?;
+                }
+                Ok(dict.into_any().unbind())
+            }
+            RowFactory::Row => Ok(Py::new(
+                py,
+                Row {
+                    columns: Arc::clone(columns),
+                    values,
+                },
+            )?
+            .into_any()),
+        }
+    }
+}
+
+fn to_py(py: Python<'_>, value: Value) -> PyObject {
+    match value {
+        Value::Null => py.None(),
+        Value::Integer(i) => PyInt::new(py, i).into_any().unbind(),
+        Value::Real(f) => PyFloat::new(py, f).into_any().unbind(),
+        Value::Text(t) => PyString::new(py, &t).into_any().unbind(),
+        Value::Blob(b) => PyBytes::new(py, &b).into_any().unbind(),
+    }
+}
+
+fn read_values(py: Python<'_>, row: &rusqlite::Row<'_>, count: usize) -> PyResult<Vec<PyObject>> {
+    (0..count)
+        .map(|i| Ok(to_py(py, row.get(i).map_err(sqlite_err)?)))
+        .collect()
+}
+
+/// A result row that is both a sequence and keyed by column name, like a
+/// namedtuple: `row[0]`, `row["name"]` and `row.name` all work.
+#[pyclass(module = "externkit.sqlite3", sequence, frozen)]
+pub struct Row {
+    columns: Arc<Vec<String>>,
+    values: Vec<PyObject>,
+}
+
+impl Row {
+    fn position(&self, name: &str) -> Option<usize> {
+        self.columns
+            .iter()
+            .position(|column| column == name)
+            .or_else(|| {
+                self.columns
+                    .iter()
+                    .position(|column| column.eq_ignore_ascii_case(name))
+            })
+    }
+
+    fn as_tuple<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyTuple>> {
+        PyTuple::new(py, self.values.iter().map(|value| value.clone_ref(py)))
+    }
+}
+
+#[pymethods]
+impl Row {
+    fn __len__(&self) -> usize {
+        self.values.len()
+    }
+
+    /// An index, a slice or a column name; names match case-insensitively
+    /// when no column has the exact name.
+    fn __getitem__(&self, py: Python<'_>, key: &Bound<'_, PyAny>) -> PyResult<PyObject> {
+        if let Ok(name) = key.extract::<String>() {
+            return self
+                .position(&name)
+                .map(|index| self.values[index].clone_ref(py))
+                .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyKeyError, _>(name));
+        }
+        if key.is_instance_of::<PyInt>() {
+            let index: isize = key.extract()?;
+            let len = self.values.len() as isize;
+            let index = if index < 0 { index + len } else { index };
+            if !(0..len).contains(&index) {
+                return Err(PyErr::new::<pyo3::exceptions::PyIndexError, _>(
+                    "Row index out of range.",
+                ));
+            }
+            return Ok(self.values[index as usize].clone_ref(py));
+        }
+        Ok(self.as_tuple(py)?.as_any().get_item(key)?.unbind())
+    }
+
+    fn __getattr__(&self, py: Python<'_>, name: &str) -> PyResult<PyObject> {
+        self.position(name)
+            .map(|index| self.values[index].clone_ref(py))
+            .ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyAttributeError, _>(format!(
+                    "Row has no column '{}'",
+                    name
+                ))
+            })
+    }
+
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, pyo3::types::PyIterator>> {
+        self.as_tuple(py)?.try_iter()
+    }
+
+    fn __contains__(&self, py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<bool> {
+        self.as_tuple(py)?.contains(value)
+    }
+
+    /// Equal to another row or a tuple with the same values.
+    fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<bool> {
+        match other.downcast::<Row>() {
+            Ok(other) => self.as_tuple(py)?.eq(other.get().as_tuple(py)?),
+            Err(_) => self.as_tuple(py)?.eq(other),
+        }
+    }
+
+    fn __hash__(&self, py: Python<'_>) -> PyResult<isize> {
+        self.as_tuple(py)?.hash()
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        let fields = self
+            .columns
+            .iter()
+            .zip(&self.values)
+            .map(|(column, value)| Ok(format!("{}={}", column, value.bind(py).repr()?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(format!("Row({})", fields.join(", ")))
+    }
+
+    fn keys(&self) -> Vec<String> {
+        self.columns.to_vec()
+    }
+
+    fn _asdict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        for (column, value) in self.columns.iter().zip(&self.values) {
+            dict.set_item(column, value)?;
+        }
+        Ok(dict)
+    }
+}
+
+/// A statement part way through its results. `rows` borrows `*stmt`, which
+/// borrows the connection of the `Cursor` it belongs to; neither lifetime is
+/// really `'static`.
+struct Pending {
+    rows: ManuallyDrop<rusqlite::Rows<'static>>,
+    stmt: NonNull<rusqlite::Statement<'static>>,
+}
+
+// SAFETY: a `Pending` is only stepped or dropped while its cursor holds the
+// connection's lock, so the statement is never used by two threads at once.
+unsafe impl Send for Pending {}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        // SAFETY: `rows` is not used again. Dropping it resets the statement,
+        // which is then finalized; `stmt` came from `Box::leak` and nothing
+        // borrows it any more.
+        unsafe {
+            ManuallyDrop::drop(&mut self.rows);
+            drop(Box::from_raw(self.stmt.as_ptr()));
+        }
+    }
+}
+
+/// Reads the rows of a statement lazily, like a DB-API cursor. The cursor
+/// keeps the connection alive and locks it for every step, so other calls
+/// on the client may run between fetches.
+#[pyclass(module = "externkit.sqlite3")]
+pub struct Cursor {
+    client: Arc<ReentrantMutex<rusqlite::Connection>>,
+    pending: Mutex<Option<Pending>>,
+    /// The row `execute` read ahead.
+    first: Option<Vec<PyObject>>,
+    columns: Arc<Vec<String>>,
+    row_factory: RowFactory,
+    /// How many rows `fetchmany()` returns by default.
+    #[pyo3(get, set)]
+    arraysize: usize,
+}
+
+impl Cursor {
+    fn open(
+        py: Python<'_>,
+        client: &Arc<ReentrantMutex<rusqlite::Connection>>,
+        sql: &str,
+        params: &Params,
+        row_factory: RowFactory,
+    ) -> PyResult<Self> {
+        let conn = lock(py, client);
+        // SAFETY: the connection lives in the `Arc` the cursor keeps until
+        // `Drop for Cursor` has dropped the statement.
+        let conn: &'static rusqlite::Connection =
+            unsafe { &*(&*conn as *const rusqlite::Connection) };
+        let mut stmt = conn.prepare(sql).map_err(sqlite_err)?;
+        let columns: Arc<Vec<String>> = Arc::new(
+            stmt.column_names()
+                .into_iter()
+                .map(str::to_string)
+                .collect(),
+        );
+        params.bind(&mut stmt)?;
+        let stmt = NonNull::from(Box::leak(Box::new(stmt)));
+        // SAFETY: `stmt` stays at this address until `Pending` is dropped,
+        // which drops `rows` first.
+        let rows = unsafe { (*stmt.as_ptr()).raw_query() };
+        Ok(Cursor {
+            client: Arc::clone(client),
+            pending: Mutex::new(Some(Pending {
+                rows: ManuallyDrop::new(rows),
+                stmt,
+            })),
+            first: None,
+            columns,
+            row_factory,
+            arraysize: 1,
+        })
+    }
+
+    /// Steps to the next row. The statement is finalized as soon as it has
+    /// no more rows or fails.
+    fn next_values(&mut self, py: Python<'_>) -> PyResult<Option<Vec<PyObject>>> {
+        if let Some(values) = self.first.take() {
+            return Ok(Some(values));
+        }
+        let _conn = lock(py, &self.client);
+        let pending = self.pending.get_mut().unwrap();
+        let Some(state) = pending else {
+            return Ok(None);
+        };
+        let values = match state.rows.next() {
+            Ok(Some(row)) => read_values(py, row, self.columns.len()).map(Some),
+            Ok(None) => Ok(None),
+            Err(e) => Err(sqlite_err(e)),
+        };
+        if !matches!(values, Ok(Some(_))) {
+            *pending = None;
+        }
+        values
+    }
+
+    fn next_row(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        match self.next_values(py)? {
+            Some(values) => Ok(Some(self.row_factory.make(py, &self.columns, values)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Drop for Cursor {
+    fn drop(&mut self) {
+        Python::with_gil(|py| {
+            let _conn = lock(py, &self.client);
+            if let Ok(pending) = self.pending.get_mut() {
+                pending.take();
+            }
+        });
+    }
+}
+
+#[pymethods]
+impl Cursor {
+    /// The next row, or `None` when there are no more.
+    fn fetchone(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        self.next_row(py)
+    }
+
+    /// Up to `size` rows, `arraysize` by default; fewer only at the end.
+    #[pyo3(signature = (size = None))]
+    fn fetchmany(&mut self, py: Python<'_>, size: Option<usize>) -> PyResult<Vec<PyObject>> {
+        let size = size.unwrap_or(self.arraysize);
+        let mut rows = Vec::with_capacity(size.min(1024));
+        while rows.len() < size {
+            match self.next_row(py)? {
+                Some(row) => rows.push(row),
+                None => break,
+            }
+        }
+        Ok(rows)
+    }
+
+    /// The remaining rows.
+    fn fetchall(&mut self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let mut rows = Vec::new();
+        while let Some(row) = self.next_row(py)? {
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        self.next_row(py)
+    }
+
+    /// A DB-API 7-tuple per column; only the name is filled in, as with
+    /// Python's `sqlite3`.
+    #[getter]
+    fn description<'py>(&self, py: Python<'py>) -> PyResult<Vec<Bound<'py, PyTuple>>> {
+        self.columns
+            .iter()
+            .map(|column| {
+                PyTuple::new(
+                    py,
+                    [
+                        PyString::new(py, column).into_any(),
+                        py.None().into_bound(py),
+                        py.None().into_bound(py),
+                        py.None().into_bound(py),
+                        py.None().into_bound(py),
+                        py.None().into_bound(py),
+                        py.None().into_bound(py),
+                    ],
+                )
+            })
+            .collect()
+    }
+
+    /// Finalizes the statement without reading the remaining rows.
+    fn close(&mut self, py: Python<'_>) {
+        self.first = None;
+        let _conn = lock(py, &self.client);
+        self.pending.get_mut().unwrap().take();
+    }
+}
+
 #[pyclass]
 pub struct SqliteClient {
-    client: Arc<Mutex<rusqlite::Connection>>,
+    /// Reentrant so Python code called while the connection is in use, such
+    /// as a generator feeding `insert_many` from a cursor, can use it too.
+    client: Arc<ReentrantMutex<rusqlite::Connection>>,
+    row_factory: RowFactory,
 }
 
 #[pymethods]
 impl SqliteClient {
     #[new]
-    fn new(connection_string: &str) -> Self {
-        let connection = rusqlite::Connection::open(connection_string).unwrap();    
-        SqliteClient {
-            client: Arc::new(Mutex::new(connection)),
-        }
+    #[pyo3(signature = (connection_string, row_factory = None))]
+    fn new(connection_string: &str, row_factory: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let row_factory = row_factory
+            .map(RowFactory::from_py)
+            .transpose()?
+            .unwrap_or(RowFactory::Tuple);
+        let connection = rusqlite::Connection::open(connection_string).unwrap();
+        Ok(SqliteClient {
+            client: Arc::new(ReentrantMutex::new(connection)),
+            row_factory,
+        })
     }
 
-    fn query(&self, py: Python<'_>, query: &str, params: Vec<PyObject>) -> PyResult<Vec<PyObject>> {
-        let conn = self.client.lock().unwrap();
-        let mut stmt = conn.prepare(query).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
-        
-        let converted_params: Vec<String> = params
-            .iter()
-            .map(|obj| obj.extract::<String>(py).unwrap_or_else(|_| "".to_string()))
-            .collect();
-        
-        let param_refs: Vec<&dyn rusqlite::ToSql> = converted_params
-            .iter()
-            .map(|s| s as &dyn rusqlite::ToSql)
-            .collect();
-        
-        let rows = stmt.query_map(&param_refs[..], |row| {
-            let column_count = row.as_ref().column_count();
-            let mut row_values = Vec::new();
-            
-            for i in 0..column_count {
-                let value: rusqlite::types::Value = row.get(i)?;
-                row_values.push(value);
-            }
-            
-            Ok(row_values)
-        }).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
-        
+    /// Rows come back as `row_factory` builds them, the client's default
+    /// unless given. With `scalar=True` the query must return one column and
+    /// its values are returned directly.
+    #[pyo3(signature = (query, params = None, *, row_factory = None, scalar = false))]
+    fn query(
+        &self,
+        py: Python<'_>,
+        query: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        row_factory: Option<&Bound<'_, PyAny>>,
+        scalar: bool,
+    ) -> PyResult<Vec<PyObject>> {
+        let params = Params::from_py(params)?;
+        let row_factory = match row_factory {
+            Some(row_factory) => RowFactory::from_py(row_factory)?,
+            None => self.row_factory,
+        };
+
+        let mut cursor = Cursor::open(py, &self.client, query, &params, row_factory)?;
+        if scalar && cursor.columns.len() != 1 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "scalar=True needs a query returning one column, this one returns {}.",
+                cursor.columns.len()
+            )));
+        }
         let mut result = Vec::new();
-        for row in rows {
-            let row_values = row.map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
-            
-            if row_values.len() == 1 {
-                // Single column - return the value directly (backward compatibility)
-                let py_value = match &row_values[0] {
-                    rusqlite::types::Value::Null => py.None(),
-                    rusqlite::types::Value::Integer(i) => pyo3::types::PyInt::new(py, *i).into(),
-                    rusqlite::types::Value::Real(f) => pyo3::types::PyFloat::new(py, *f).into(),
-                    rusqlite::types::Value::Text(t) => pyo3::types::PyString::new(py, t).into(),
-                    rusqlite::types::Value::Blob(b) => pyo3::types::PyBytes::new(py, b).into(),
-                };
-                result.push(py_value);
+        while let Some(mut values) = cursor.next_values(py)? {
+            if scalar {
+                result.push(values.remove(0));
             } else {
-                // Multiple columns - return as a list/tuple
-                let mut py_row = Vec::new();
-                for value in row_values {
-                    let py_value = match value {
-                        rusqlite::types::Value::Null => py.None(),
-                        rusqlite::types::Value::Integer(i) => pyo3::types::PyInt::new(py, i).into(),
-                        rusqlite::types::Value::Real(f) => pyo3::types::PyFloat::new(py, f).into(),
-                        rusqlite::types::Value::Text(t) => pyo3::types::PyString::new(py, &t).into(),
-                        rusqlite::types::Value::Blob(b) => pyo3::types::PyBytes::new(py, &b).into(),
-                    };
-                    py_row.push(py_value);
-                }
-                let py_tuple = pyo3::types::PyTuple::new(py, py_row).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
-                result.push(py_tuple.into());
+                result.push(row_factory.make(py, &cursor.columns, values)?);
             }
         }
         Ok(result)
     }
 
+    /// Runs `sql` and returns a cursor that reads its rows one at a time,
+    /// for results too large to hold in a list.
+    #[pyo3(signature = (sql, params = None, *, row_factory = None))]
+    fn execute(
+        &self,
+        py: Python<'_>,
+        sql: &str,
+        params: Option<&Bound<'_, PyAny>>,
+        row_factory: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<Cursor> {
+        let params = Params::from_py(params)?;
+        let row_factory = match row_factory {
+            Some(row_factory) => RowFactory::from_py(row_factory)?,
+            None => self.row_factory,
+        };
+        let mut cursor = Cursor::open(py, &self.client, sql, &params, row_factory)?;
+        // Step once so the statement runs, and fails, here rather than at
+        // the first fetch.
+        cursor.first = cursor.next_values(py)?;
+        Ok(cursor)
+    }
+
     fn create_table(
         &self,
+        py: Python<'_>,
         table_name: &str,
         columns: Vec<(String, String)>,
     ) -> PyResult<()> {
-        let conn = self.client.lock().unwrap();
+        let conn = lock(py, &self.client);
         let columns_def: Vec<String> = columns
             .iter()
             .map(|(name, type_)| format!("{} {}", name, type_))
@@ -92,54 +700,300 @@ impl SqliteClient {
             table_name,
             columns_def.join(", ")
         );
-        conn.execute(&sql, []).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+        conn.execute(&sql, []).map_err(sqlite_err)?;
         Ok(())
     }
 
-    fn insert(&self, py: Python<'_>, table_name: &str, columns: Vec<String>, values: Vec<PyObject>) -> PyResult<()> {
-        let conn = self.client.lock().unwrap();
-        let converted_values: Vec<String> = values
-            .iter()
-            .map(|obj| obj.extract::<String>(py).unwrap_or_else(|_| "".to_string()))
-            .collect();
-        
+    fn insert(
+        &self,
+        py: Python<'_>,
+        table_name: &str,
+        columns: Vec<String>,
+        values: Vec<PyObject>,
+    ) -> PyResult<()> {
+        // Adapters run Python code, so convert before taking the lock.
+        let converted_values = to_sql_params(py, &values)?;
+        let conn = lock(py, &self.client);
+
         let columns_str = columns.join(", ");
         let placeholders = values.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
-        let sql = format!("INSERT INTO {} ({}) VALUES ({})", table_name, columns_str, placeholders);
-        
-        let param_refs: Vec<&dyn rusqlite::ToSql> = converted_values
-            .iter()
-            .map(|s| s as &dyn rusqlite::ToSql)
-            .collect();
-        
-        conn.execute(&sql, &param_refs[..]).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table_name, columns_str, placeholders
+        );
+
+        conn.execute(&sql, rusqlite::params_from_iter(&converted_values))
+            .map_err(sqlite_err)?;
         Ok(())
     }
 
-    fn select(&self, py: Python<'_>, table_name: &str, columns: Vec<String>, where_clause: Option<String>) -> PyResult<Vec<PyObject>> {
+    #[pyo3(signature = (table_name, columns, where_clause = None, *, row_factory = None, scalar = false))]
+    fn select(
+        &self,
+        py: Python<'_>,
+        table_name: &str,
+        columns: Vec<String>,
+        where_clause: Option<String>,
+        row_factory: Option<&Bound<'_, PyAny>>,
+        scalar: bool,
+    ) -> PyResult<Vec<PyObject>> {
         let columns_str = columns.join(", ");
         let sql = match where_clause {
-            Some(clause) => format!("SELECT {} FROM {} WHERE {}", columns_str, table_name, clause),
+            Some(clause) => format!(
+                "SELECT {} FROM {} WHERE {}",
+                columns_str, table_name, clause
+            ),
             None => format!("SELECT {} FROM {}", columns_str, table_name),
         };
-        self.query(py, &sql, vec![])
+        self.query(py, &sql, None, row_factory, scalar)
     }
 
-    fn delete(&self, table_name: &str, where_clause: &str) -> PyResult<usize> {
-        let conn = self.client.lock().unwrap();
+    fn delete(&self, py: Python<'_>, table_name: &str, where_clause: &str) -> PyResult<usize> {
+        let conn = lock(py, &self.client);
         let sql = format!("DELETE FROM {} WHERE {}", table_name, where_clause);
-        let rows_affected = conn.execute(&sql, []).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+        let rows_affected = conn.execute(&sql, []).map_err(sqlite_err)?;
         Ok(rows_affected)
     }
 
-    fn update(&self, table_name: &str, set_clause: &str, where_clause: &str) -> PyResult<usize> {
-        let conn = self.client.lock().unwrap();
-        let sql = format!("UPDATE {} SET {} WHERE {}", table_name, set_clause, where_clause);
-        let rows_affected = conn.execute(&sql, []).map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("{}", e)))?;
+    fn update(
+        &self,
+        py: Python<'_>,
+        table_name: &str,
+        set_clause: &str,
+        where_clause: &str,
+    ) -> PyResult<usize> {
+        let conn = lock(py, &self.client);
+        let sql = format!(
+            "UPDATE {} SET {} WHERE {}",
+            table_name, set_clause, where_clause
+        );
+        let rows_affected = conn.execute(&sql, []).map_err(sqlite_err)?;
         Ok(rows_affected)
     }
 
+    /// Runs `sql` once for every parameter set `seq_of_params` yields, with
+    /// one prepared statement, and returns the total number of rows changed.
+    /// The iterable is consumed lazily, and all of it is applied or none:
+    /// the batch runs in a transaction, or a savepoint inside an active one.
+    fn executemany(&self, sql: &str, seq_of_params: &Bound<'_, PyAny>) -> PyResult<usize> {
+        let conn = lock(seq_of_params.py(), &self.client);
+        atomically(&conn, |conn| execute_many(conn, sql, seq_of_params))
+    }
+
+    /// Inserts every row of `rows`, any iterable of value sequences, like
+    /// `executemany` does.
+    fn insert_many(
+        &self,
+        table_name: &str,
+        columns: Vec<String>,
+        rows: &Bound<'_, PyAny>,
+    ) -> PyResult<usize> {
+        let placeholders = columns.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            table_name,
+            columns.join(", "),
+            placeholders
+        );
+        let conn = lock(rows.py(), &self.client);
+        atomically(&conn, |conn| execute_many(conn, &sql, rows))
+    }
+
+    /// Starts a transaction; `mode` is `"deferred"`, `"immediate"` or
+    /// `"exclusive"`, as in `BEGIN <mode>`.
+    #[pyo3(signature = (mode = "deferred"))]
+    fn begin(&self, py: Python<'_>, mode: &str) -> PyResult<()> {
+        let sql = begin_sql(mode)?;
+        let conn = lock(py, &self.client);
+        if !conn.is_autocommit() {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "A transaction is already active. Use transaction() to nest one.",
+            ));
+        }
+        conn.execute_batch(sql).map_err(sqlite_err)
+    }
+
+    /// Commits the active transaction; does nothing without one.
+    fn commit(&self, py: Python<'_>) -> PyResult<()> {
+        let conn = lock(py, &self.client);
+        if conn.is_autocommit() {
+            return Ok(());
+        }
+        conn.execute_batch("COMMIT").map_err(sqlite_err)
+    }
+
+    /// Rolls back the active transaction; does nothing without one.
+    fn rollback(&self, py: Python<'_>) -> PyResult<()> {
+        let conn = lock(py, &self.client);
+        if conn.is_autocommit() {
+            return Ok(());
+        }
+        conn.execute_batch("ROLLBACK").map_err(sqlite_err)
+    }
+
+    #[getter]
+    fn in_transaction(&self, py: Python<'_>) -> bool {
+        !lock(py, &self.client).is_autocommit()
+    }
+
+    /// A context manager that begins a transaction, or a savepoint when one
+    /// is already active, and commits it when the block succeeds or rolls it
+    /// back when the block raises.
+    #[pyo3(signature = (mode = "deferred"))]
+    fn transaction(&self, mode: &str) -> PyResult<Transaction> {
+        Ok(Transaction {
+            client: Arc::clone(&self.client),
+            begin: begin_sql(mode)?,
+            savepoint: None,
+            active: false,
+        })
+    }
+
     fn close(&mut self) -> PyResult<()> {
         Ok(())
     }
-}   
\ No newline at end of file
+}
+
+fn begin_sql(mode: &str) -> PyResult<&'static str> {
+    match mode.to_ascii_lowercase().as_str() {
+        "deferred" => Ok("BEGIN DEFERRED"),
+        "immediate" => Ok("BEGIN IMMEDIATE"),
+        "exclusive" => Ok("BEGIN EXCLUSIVE"),
+        _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "Unknown transaction mode '{}'. Use 'deferred', 'immediate' or 'exclusive'.",
+            mode
+        ))),
+    }
+}
+
+/// Numbers savepoints so nested blocks never release each other's.
+static SAVEPOINTS: AtomicUsize = AtomicUsize::new(0);
+
+fn next_savepoint() -> String {
+    format!(
+        "externkit_sp_{}",
+        SAVEPOINTS.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Runs `f` in a transaction, or in a savepoint when one is already active,
+/// and undoes everything it did when it fails.
+fn atomically<T>(
+    conn: &rusqlite::Connection,
+    f: impl FnOnce(&rusqlite::Connection) -> PyResult<T>,
+) -> PyResult<T> {
+    let savepoint = if conn.is_autocommit() {
+        conn.execute_batch("BEGIN").map_err(sqlite_err)?;
+        None
+    } else {
+        let name = next_savepoint();
+        conn.execute_batch(&format!("SAVEPOINT {}", name))
+            .map_err(sqlite_err)?;
+        Some(name)
+    };
+    let result = f(conn);
+    let end = match (&savepoint, &result) {
+        (Some(name), Ok(_)) => format!("RELEASE SAVEPOINT {}", name),
+        (Some(name), Err(_)) => {
+            format!("ROLLBACK TO SAVEPOINT {name}; RELEASE SAVEPOINT {name}")
+        }
+        (None, Ok(_)) => "COMMIT".to_string(),
+        (None, Err(_)) => "ROLLBACK".to_string(),
+    };
+    if let Err(e) = conn.execute_batch(&end) {
+        if savepoint.is_none() && !conn.is_autocommit() {
+            let _ = conn.execute_batch("ROLLBACK");
+        }
+        return Err(result.err().unwrap_or_else(|| sqlite_err(e)));
+    }
+    result
+}
+
+/// Prepares `sql` once and executes it for each item of `seq_of_params`,
+/// pulling the items one at a time.
+fn execute_many(
+    conn: &rusqlite::Connection,
+    sql: &str,
+    seq_of_params: &Bound<'_, PyAny>,
+) -> PyResult<usize> {
+    let mut stmt = conn.prepare(sql).map_err(sqlite_err)?;
+    let mut changed = 0;
+    for params in seq_of_params.try_iter()? {
+        Params::from_py(Some(&params?))?.bind(&mut stmt)?;
+        changed += stmt.raw_execute().map_err(sqlite_err)?;
+    }
+    Ok(changed)
+}
+
+/// What `SqliteClient.transaction()` returns. The outermost block owns the
+/// transaction; blocks nested inside it use savepoints, so an inner failure
+/// only undoes the inner block.
+#[pyclass(module = "externkit.sqlite3")]
+pub struct Transaction {
+    client: Arc<ReentrantMutex<rusqlite::Connection>>,
+    begin: &'static str,
+    savepoint: Option<String>,
+    active: bool,
+}
+
+#[pymethods]
+impl Transaction {
+    fn __enter__(mut slf: PyRefMut<'_, Self>) -> PyResult<PyRefMut<'_, Self>> {
+        if slf.active {
+            return Err(PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(
+                "This transaction block is already in use.",
+            ));
+        }
+        let savepoint = {
+            let conn = lock(slf.py(), &slf.client);
+            if conn.is_autocommit() {
+                conn.execute_batch(slf.begin).map_err(sqlite_err)?;
+                None
+            } else {
+                let name = next_savepoint();
+                conn.execute_batch(&format!("SAVEPOINT {}", name))
+                    .map_err(sqlite_err)?;
+                Some(name)
+            }
+        };
+        slf.savepoint = savepoint;
+        slf.active = true;
+        Ok(slf)
+    }
+
+    /// Never suppresses the exception that ended the block.
+    fn __exit__(
+        &mut self,
+        py: Python<'_>,
+        exc_type: Option<&Bound<'_, PyAny>>,
+        _exc_value: Option<&Bound<'_, PyAny>>,
+        _traceback: Option<&Bound<'_, PyAny>>,
+    ) -> PyResult<bool> {
+        if !std::mem::take(&mut self.active) {
+            return Ok(false);
+        }
+        let failed = exc_type.is_some_and(|exc_type| !exc_type.is_none());
+        let conn = lock(py, &self.client);
+        match self.savepoint.take() {
+            Some(name) if failed => conn
+                .execute_batch(&format!(
+                    "ROLLBACK TO SAVEPOINT {name}; RELEASE SAVEPOINT {name}"
+                ))
+                .map_err(sqlite_err)?,
+            Some(name) => conn
+                .execute_batch(&format!("RELEASE SAVEPOINT {}", name))
+                .map_err(sqlite_err)?,
+            // The block may have ended the transaction itself with commit()
+            // or rollback().
+            None if conn.is_autocommit() => {}
+            None if failed => conn.execute_batch("ROLLBACK").map_err(sqlite_err)?,
+            None => {
+                if let Err(e) = conn.execute_batch("COMMIT") {
+                    let _ = conn.execute_batch("ROLLBACK");
+                    return Err(sqlite_err(e));
+                }
+            }
+        }
+        Ok(false)
+    }
+}